@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use prefix::{parse_fix_msg, Field, FixMsg};
+use regex::Regex;
+use std::borrow::Cow;
+
+const LOG: &str = include_str!("../test/test.txt");
+
+// The regex based parser that was used before the byte level parser, kept to compare against.
+fn parse_fix_msg_regex<'a>(input: &'a str, regex: &Regex) -> FixMsg<'a> {
+    if !regex.is_match(input) {
+        return FixMsg::None;
+    }
+
+    let mut contains_begin_string = false;
+    let mut contains_check_sum = false;
+
+    let mut result = Vec::new();
+    for i in regex.captures_iter(input) {
+        let tag = i["tag"]
+            .parse()
+            .unwrap_or_else(|_| panic!("could not parse tag: {}", &i["tag"]));
+        if tag == 8 {
+            contains_begin_string = true;
+        } else if tag == 10 {
+            contains_check_sum = true;
+        }
+        result.push(Field {
+            tag,
+            value: Cow::Owned(i["value"].to_string()),
+        })
+    }
+    if !contains_begin_string || !contains_check_sum {
+        return FixMsg::Partial(result);
+    }
+    FixMsg::Full(result)
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let log = LOG.repeat(1000);
+    let regex = Regex::new(r"(?P<tag>[0-9]+)=(?P<value>[^\^\|\x01]+)").unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(log.len() as u64));
+    group.bench_function("bytes", |b| {
+        b.iter(|| {
+            for line in log.lines() {
+                black_box(parse_fix_msg(black_box(line)));
+            }
+        })
+    });
+    group.bench_function("regex", |b| {
+        b.iter(|| {
+            for line in log.lines() {
+                black_box(parse_fix_msg_regex(black_box(line), &regex));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,187 @@
+use crate::{
+    diff, log_format,
+    tags::{self, MSG_TYPE, ORDER_ID},
+    Field, Printer,
+};
+use std::{collections::HashMap, ptr, sync::Arc};
+
+const CL_ORD_ID: usize = 11;
+const ORIG_CL_ORD_ID: usize = 41;
+
+type Message = Arc<Vec<Field<'static>>>;
+
+// OrdStatus values after which an order sees no more changes: Filled, Canceled, Rejected and
+// Expired.
+const TERMINAL: [&str; 4] = ["2", "4", "8", "C"];
+const ORD_STATUS: usize = 39;
+
+// The last message seen for each order still open, so each new message can be compared to it.
+// Orders are forgotten once they reach a terminal state, so memory follows the open orders rather
+// than the length of the log.
+#[derive(Default)]
+pub struct Orders {
+    count: usize,
+    by_id: HashMap<(usize, String), usize>,
+    orders: HashMap<usize, Order>,
+}
+
+struct Order {
+    // When the last message of the order was seen.
+    seen: usize,
+    message: Message,
+    ids: Vec<(usize, String)>,
+}
+
+impl Orders {
+    // Finds the order sharing a ClOrdID or OrderID with this message that was seen last, a replace
+    // is linked to the order it replaces through its OrigClOrdID.
+    fn find(&self, fix_msg: &[Field]) -> Option<usize> {
+        fix_msg
+            .iter()
+            .filter_map(|field| match field.tag {
+                CL_ORD_ID | ORDER_ID => Some((field.tag, field)),
+                ORIG_CL_ORD_ID => Some((CL_ORD_ID, field)),
+                _ => None,
+            })
+            .filter_map(|(tag, field)| self.by_id.get(&(tag, field.value.to_string())))
+            .max_by_key(|order| self.orders[order].seen)
+            .copied()
+    }
+
+    fn update(&mut self, order: Option<usize>, fix_msg: &[Field]) {
+        self.count += 1;
+        let terminal = fix_msg
+            .iter()
+            .any(|field| field.tag == ORD_STATUS && TERMINAL.contains(&field.value.trim()));
+        if terminal {
+            if let Some((key, order)) = order.and_then(|key| self.orders.remove_entry(&key)) {
+                // An ID taken over by a later order stays with it.
+                for id in order.ids {
+                    if self.by_id.get(&id) == Some(&key) {
+                        self.by_id.remove(&id);
+                    }
+                }
+            }
+            return;
+        }
+        // Messages that are not for an order, such as heartbeats, are not kept.
+        let has_id = fix_msg
+            .iter()
+            .any(|field| field.tag == CL_ORD_ID || field.tag == ORDER_ID);
+        if order.is_none() && !has_id {
+            return;
+        }
+        let key = order.unwrap_or(self.count);
+        let message = Arc::new(fix_msg.iter().map(|f| f.clone().into_owned()).collect());
+        let order = self.orders.entry(key).or_insert_with(|| Order {
+            seen: 0,
+            message: Arc::clone(&message),
+            ids: Vec::new(),
+        });
+        order.seen = self.count;
+        order.message = message;
+        for field in fix_msg {
+            if field.tag == CL_ORD_ID || field.tag == ORDER_ID {
+                let id = (field.tag, field.value.to_string());
+                if !order.ids.contains(&id) {
+                    order.ids.push(id.clone());
+                }
+                self.by_id.insert(id, key);
+            }
+        }
+    }
+}
+
+impl Printer {
+    // Reduces a message to the fields that changed since the previous message for the same order,
+    // keeping the MsgType and order ID so it is clear which message it was. Header and trailer
+    // fields such as MsgSeqNum and SendingTime change with every message so they are left out,
+    // along with what the log says about each message.
+    pub(crate) fn changed_fields<'a>(&self, fix_msg: &[Field<'a>]) -> Vec<Field<'a>> {
+        let mut orders = self.orders.lock().unwrap();
+        let order = orders.find(fix_msg);
+        let previous = order.map(|order| Arc::clone(&orders.orders[&order].message));
+        orders.update(order, fix_msg);
+        drop(orders);
+
+        let Some(previous) = previous else {
+            return fix_msg.to_vec();
+        };
+        let id_tag = if fix_msg.iter().any(|field| field.tag == CL_ORD_ID) {
+            CL_ORD_ID
+        } else {
+            ORDER_ID
+        };
+        let changed: Vec<&Field> = diff(&previous, fix_msg)
+            .into_iter()
+            .filter_map(|diff| diff.right)
+            .filter(|field| {
+                !tags::HEADER.contains(&field.tag)
+                    && !tags::TRAILER.contains(&field.tag)
+                    && !log_format::is_log_field(field.tag)
+            })
+            .collect();
+        fix_msg
+            .iter()
+            .filter(|field| {
+                field.tag == MSG_TYPE
+                    || field.tag == id_tag
+                    || changed.iter().any(|changed| ptr::eq(*changed, *field))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{run_lines, run_with},
+        Options, Printer,
+    };
+
+    #[test]
+    fn changes_case() {
+        let options = Options {
+            delimiter: String::from("|"),
+            strip: true,
+            changes: true,
+            ..Options::default()
+        };
+        let lines = [
+            "35=D|34=1|11=A|55=EUR/USD|38=100",
+            "35=8|34=2|11=A|37=B|55=EUR/USD|38=100|39=0|14=0",
+            "35=8|34=3|37=B|55=EUR/USD|38=100|39=1|14=50",
+            "35=G|34=4|11=C|41=A|38=200",
+            "35=D|34=5|11=D|55=EUR/USD",
+            "35=8|34=6|11=C|37=B|38=200|39=2|14=200",
+            "35=8|34=7|11=C|37=B|38=200|39=2|14=200",
+        ];
+        // A filled order is forgotten, so a later message for it is printed in full.
+        let expected = "MsgType=D|MsgSeqNum=1|ClOrdID=A|Symbol=EUR/USD|OrderQty=100|\n\
+            MsgType=8|ClOrdID=A|OrderID=B|OrdStatus=0|CumQty=0|\n\
+            MsgType=8|OrderID=B|OrdStatus=1|CumQty=50|\n\
+            MsgType=G|ClOrdID=C|OrigClOrdID=A|OrderQty=200|\n\
+            MsgType=D|MsgSeqNum=5|ClOrdID=D|Symbol=EUR/USD|\n\
+            MsgType=8|ClOrdID=C|OrderID=B|OrdStatus=2|CumQty=200|\n\
+            MsgType=8|MsgSeqNum=7|ClOrdID=C|OrderID=B|OrderQty=200|OrdStatus=2|CumQty=200|\n";
+        assert_eq!(run_lines(options, lines), expected);
+    }
+
+    #[test]
+    fn orders_case() {
+        let printer = Printer::new(Options {
+            changes: true,
+            ..Options::default()
+        });
+        run_with(
+            &printer,
+            (1..=100).map(|seq_num| format!("35=0|34={seq_num}")),
+        );
+        run_with(&printer, ["35=D|11=A|38=100", "35=8|11=A|37=X|39=0"]);
+        assert_eq!(printer.orders.lock().unwrap().orders.len(), 1);
+        run_with(&printer, ["35=8|37=X|39=2"]);
+        let orders = printer.orders.lock().unwrap();
+        assert!(orders.orders.is_empty() && orders.by_id.is_empty());
+    }
+}
@@ -1,30 +1,145 @@
-use clap::{arg, Arg, ArgAction, Command};
+use clap::{arg, value_parser, Arg, ArgAction, Command};
+
+fn make_diff_command() -> Command {
+    Command::new("diff")
+        .about("Compare two FIX messages, printing the fields that were removed, added or changed")
+        .arg(
+            Arg::new("left")
+                .required(true)
+                .help("The FIX message to compare against"),
+        )
+        .arg(
+            Arg::new("right")
+                .required(true)
+                .help("The FIX message to compare"),
+        )
+}
+
+fn make_reconcile_command() -> Command {
+    Command::new("reconcile")
+        .about("Match the fills in two logs by ExecID, or by OrderID, LastQty and LastPx, printing the fills that are missing, extra or disagree")
+        .arg(
+            Arg::new("ours")
+                .required(true)
+                .help("Our own log"),
+        )
+        .arg(
+            Arg::new("theirs")
+                .required(true)
+                .help("The counterparty or drop copy log to reconcile against"),
+        )
+}
+
+fn make_merge_command() -> Command {
+    Command::new("merge")
+        .about("Interleave several logs into one in order of SendingTime, or the time given by --time-source, labelling each line with the log it came from")
+        .arg(
+            Arg::new("logs")
+                .required(true)
+                .num_args(1..)
+                .help("The logs to merge, labelled by their file name or written as LABEL=PATH"),
+        )
+}
 
 pub fn make_command() -> Command {
     Command::new("prefix")
         .about("A customizable pretty printer for FIX messages")
         .version(env!("CARGO_PKG_VERSION"))
+        // A flag given more than once takes its last value.
+        .args_override_self(true)
+        .subcommand(make_diff_command())
+        .subcommand(make_reconcile_command())
+        .subcommand(make_merge_command())
         .arg(Arg::new("message").num_args(1..).help(
             "FIX message to be parsed, if not provided will look for a message piped through stdin",
         ))
         .arg(
-            arg!(-c --color <when> "Adds colour to the delimiter and = in for FIX fields, auto will colour only when printing directly into a tty")
+            arg!(-c --color <when> "Adds colour to FIX fields, auto will colour only when printing directly into a tty and NO_COLOR is not set. Colours are set with PREFIX_COLORS, for example 'tag=1;34:unknown=2:value=:translated=32:separator=33:header=2'")
                 .alias("colour")
+                .global(true)
                 .value_parser(["always", "auto", "never"])
                 .default_value("auto"),
         )
+        .arg(
+            arg!(-C --changes "Only print the fields that changed since the last message for the same order, matched by ClOrdID, OrigClOrdID or OrderID")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("summary")
+        )
         .arg(
             arg!(-d --delimiter <delimiter> "Set delimiter string to print after each FIX field")
                 .default_value("\n")
         )
+        .arg(
+            arg!(-F --file <file> "Read FIX messages from a file, large files are split into chunks that are processed in parallel")
+                .action(ArgAction::Append)
+                .conflicts_with("message")
+        )
+        .arg(
+            arg!(-H --highlight <field> "Highlight fields when colour is used, given as TAG or TAG=VALUE where both can be names or numbers, for example 'Symbol' or '11=ABC123'")
+                .action(ArgAction::Append)
+        )
         .arg(
             arg!(-o --"only-fix" "Only print FIX messages")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            arg!(-P --profile <name> "Use the flags from a profile in the config file, ~/.config/prefix/config.toml")
+        )
+        .arg(
+            arg!(--"no-config" "Ignore the default flags and profiles in the config file")
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             arg!(--porcelain "print FIX messages closer to standard format, same as --delimiter \\x01 --strip")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            arg!(--direction "Print → before each message that was sent and ← before each that was received, followed by the counterparty, using the Direction read by --log-format")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--me <compid> "Our CompID, so messages with it as their SenderCompID are printed as sent and as their TargetCompID as received, the same as --direction")
+        )
+        .arg(
+            arg!(--"resolve-rejects" "Print the earlier message that each Reject or BusinessMessageReject refers to by its RefSeqNum under it, with the field named by RefTagID highlighted, or marked with ^ without colour")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--time <format> "Print UTCTimestamp and UTCTimeOnly fields such as SendingTime in a time zone like 'Europe/London', 'local' or '+09:00', as seconds relative to the first message with 'relative', or as nanoseconds since the unix epoch with 'epoch'")
+        )
+        .arg(
+            arg!(--since <time> "Only print or count messages at or after a time, such as '2024-01-01T10:00:00', '20240101-10:00:00.250' or '10:00' for the date of the first message")
+        )
+        .arg(
+            arg!(--until <time> "Only print or count messages at or before a time, written the same as --since")
+        )
+        .arg(
+            arg!(--"time-source" <tag> "The tag holding the time compared to --since and --until, SendingTime by default, or 'line' for the first timestamp on the line before the FIX message")
+        )
+        .arg(
+            arg!(--"log-format" <format> "Read the time, direction and session a FIX engine logs before each message into the LogTime, Direction and Session fields. Either quickfixj, quickfixn, onixs, auto for any of them, or a regex with the named groups time, direction and session such as '^(?P<time>\\S+) (?P<direction>IN|OUT) '. Formats are tried in the order given")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            arg!(--define <field> "Compute a field from the others, written as NAME=EXPRESSION, for example 'Notional=OrderQty * Price'. Expressions use tag names or #NUMBER, numbers, \"text\", + - * /, == != < <= > >=, && || ! and brackets, subtracting two timestamps gives seconds. The name can be used in --summary, --stats, --aggregate, --filter and later definitions")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            arg!(--filter <expression> "Only print or count the FIX messages where the expression is true, written the same as for --define, for example 'Side == \"Buy\" && OrderQty > 1000'")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            arg!(--rule <rule> "Colour the value of a field when it matches, written as TAG=VALUE:STYLE where STYLE is SGR parameters, for example 'Side=Buy:32'")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            arg!(--"header-rule" <rule> "Colour the header of a message containing a matching field, written the same as --rule")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            arg!(--semantic "Colour sides, rejected orders and the header of reject messages, after any rules given with --rule")
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             arg!(-r --repeating "Combine any repeating groups into a single field with a comma delimited value")
                 .action(ArgAction::SetTrue)
@@ -36,11 +151,50 @@ pub fn make_command() -> Command {
         .arg(
             arg!(-s --strip "Strip the whitespace around the = in each field")
                 .action(ArgAction::SetTrue)
+                .global(true)
         )
         .arg(
-            arg!(-S --summary [template] "Summarise each fix message based on a template, if summary is provided with no template then it uses '35'")
+            arg!(-S --summary [template] "Summarise each fix message based on a template such as '{MsgType} {Side:>4} {OrderQty:,} {Symbol|\"?\"}', fields are tag names or numbers with an optional default and format. Templates without braces replace each tag number. Written as TYPE=TEMPLATE the template is only used for that MsgType, such as 'D={Symbol}' or '8={OrdStatus}', others use the template without a type or are printed in full. If summary is provided with no template then it uses '35'")
+                .action(ArgAction::Append)
                 .default_missing_value("35")
         )
+        .arg(
+            arg!(--sessions "Report the Logon and Logout messages of each session, gaps between messages longer than the HeartBtInt and TestRequests without a Heartbeat, instead of printing messages")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["summary", "changes", "stats"])
+        )
+        .arg(
+            arg!(--output <format> "Print the messages as a sequence diagram between their SenderCompIDs and TargetCompIDs, with each arrow labelled by the MsgType followed by the --summary of the message")
+                .value_parser(["text", "mermaid", "plantuml"])
+                .conflicts_with_all(["sessions", "stats", "split-by"])
+        )
+        .arg(
+            arg!(--"split-by" <tags> "Write each FIX message to a file named after its values of one or more tags instead of printing it, for example 'SenderCompID,TargetCompID' for a file for each session")
+                .conflicts_with_all(["sessions", "stats"])
+        )
+        .arg(
+            arg!(--"out-dir" <dir> "The directory to write the files of --split-by to, the current directory by default")
+                .requires("split-by")
+        )
+        .arg(
+            arg!(--raw "Write the lines of --split-by as they were read instead of printing them")
+                .action(ArgAction::SetTrue)
+                .requires("split-by")
+        )
+        .arg(
+            arg!(--stats <tags> "Count the FIX messages for each value of one or more tags instead of printing them, for example 'MsgType,Symbol'")
+                .conflicts_with_all(["summary", "changes"])
+        )
+        .arg(
+            arg!(--aggregate <aggregate> "Add a column to --stats, written as FUNCTION:TAG where FUNCTION is sum, avg, min, max or vwap (weighted by LastQty, or vwap:TAG:WEIGHT), for example 'sum:LastQty'")
+                .action(ArgAction::Append)
+                .requires("stats")
+        )
+        .arg(
+            arg!(--csv "Print --stats as CSV instead of a table")
+                .action(ArgAction::SetTrue)
+                .requires("stats")
+        )
         .arg(
             arg!(-t --tag "Translate tag numbers on non FIX message lines, if the entire line matches a tag name it will print it's number")
                 .action(ArgAction::SetTrue)
@@ -49,4 +203,21 @@ pub fn make_command() -> Command {
             arg!(-v --value "Translate the values of some tags (for Side: 1 -> Buy)")
                 .action(ArgAction::SetTrue)
         )
+        .mut_args(switch)
+}
+
+// Flags can also be given as --flag=false, to turn off one set by the config file.
+fn switch(arg: Arg) -> Arg {
+    if !matches!(arg.get_action(), ArgAction::SetTrue) {
+        return arg;
+    }
+    arg.action(ArgAction::Set)
+        .num_args(0..=1)
+        .require_equals(true)
+        .value_name("BOOL")
+        .value_parser(value_parser!(bool))
+        .default_value("false")
+        .default_missing_value("true")
+        .hide_default_value(true)
+        .hide_possible_values(true)
 }
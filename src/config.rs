@@ -0,0 +1,246 @@
+use clap::{Arg, Command};
+use std::{env, fs, io, path::PathBuf};
+use toml::{Table, Value};
+
+// The config file holds default flags and named profiles, written as the long name of the flag:
+//
+// [defaults]
+// color = "always"
+// delimiter = "|"
+//
+// [profiles.ops]
+// value = true
+// repeating = true
+// summary = "52 35 11 55"
+//
+// [profiles.orders.summary]
+// D = "{MsgType} {Side} {OrderQty} {Symbol}"
+// ExecutionReport = "{MsgType} {OrdStatus} {LastQty} {LastPx}"
+pub struct Config {
+    path: PathBuf,
+    table: Table,
+}
+
+impl Config {
+    pub fn load() -> Result<Option<Self>, String> {
+        let Some(path) = config_path() else {
+            return Ok(None);
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(format!("{}: {error}", path.display())),
+        };
+        let table = contents
+            .parse::<Table>()
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+        Ok(Some(Config { path, table }))
+    }
+
+    pub fn defaults(&self) -> Result<Vec<Entry>, String> {
+        match self.table.get("defaults") {
+            Some(Value::Table(defaults)) => self.to_args(defaults),
+            Some(_) => Err(format!("{}: defaults must be a table", self.path.display())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Result<Vec<Entry>, String> {
+        let profile = self
+            .table
+            .get("profiles")
+            .and_then(|profiles| profiles.get(name));
+        match profile {
+            Some(Value::Table(profile)) => self.to_args(profile),
+            Some(_) => Err(format!(
+                "{}: profile '{name}' must be a table",
+                self.path.display()
+            )),
+            None => Err(format!(
+                "profile '{name}' is not defined in {}",
+                self.path.display()
+            )),
+        }
+    }
+
+    // Turns each entry into the command line arguments it stands for, so they are validated by
+    // clap the same as if they had been typed.
+    fn to_args(&self, table: &Table) -> Result<Vec<Entry>, String> {
+        let mut args = Vec::new();
+        for (flag, value) in table {
+            let mut push = |arg| args.push((flag.clone(), arg));
+            match value {
+                Value::Boolean(true) => push(format!("--{flag}")),
+                // Written out so a profile can turn off a switch set in the defaults.
+                Value::Boolean(false) => push(format!("--{flag}=false")),
+                Value::Array(values) => {
+                    for value in values {
+                        push(format!("--{flag}={}", self.to_arg(flag, value)?));
+                    }
+                }
+                // Tables are for flags taking KEY=VALUE, such as a summary for each MsgType.
+                Value::Table(values) => {
+                    for (key, value) in values {
+                        push(format!("--{flag}={key}={}", self.to_arg(flag, value)?));
+                    }
+                }
+                value => push(format!("--{flag}={}", self.to_arg(flag, value)?)),
+            }
+        }
+        Ok(args)
+    }
+
+    fn to_arg(&self, flag: &str, value: &Value) -> Result<String, String> {
+        match value {
+            Value::String(value) => Ok(value.clone()),
+            Value::Integer(value) => Ok(value.to_string()),
+            Value::Float(value) => Ok(value.to_string()),
+            _ => Err(format!(
+                "{}: unsupported value for {flag}: {value}",
+                self.path.display()
+            )),
+        }
+    }
+}
+
+// A flag from the config and the command line argument it stands for.
+pub type Entry = (String, String);
+
+// Drops the entries for flags that are given later, or that conflict with a flag given later, so
+// the later flag overrides the config rather than clap rejecting the pair. Flags are named by their
+// ids, and entries for unknown flags are kept for clap to report.
+pub fn without_overridden(command: &Command, entries: Vec<Entry>, later: &[&str]) -> Vec<Entry> {
+    let find = |flag: &str| {
+        command.get_arguments().find(|arg| {
+            arg.get_id() == flag
+                || arg.get_long() == Some(flag)
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&flag))
+        })
+    };
+    let conflicts = |arg: &Arg, other: &str| {
+        command
+            .get_arg_conflicts_with(arg)
+            .iter()
+            .any(|conflict| conflict.get_id() == other)
+    };
+    let later: Vec<&str> = later
+        .iter()
+        .map(|&flag| find(flag).map_or(flag, |arg| arg.get_id().as_str()))
+        .collect();
+    entries
+        .into_iter()
+        .filter(|(flag, _)| {
+            let Some(arg) = find(flag) else {
+                return true;
+            };
+            let id = arg.get_id().as_str();
+            !later.iter().any(|&other| {
+                other == id
+                    || conflicts(arg, other)
+                    || find(other).is_some_and(|other| conflicts(other, id))
+            })
+        })
+        .collect()
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("PREFIX_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("prefix").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_case() {
+        let config = Config {
+            path: PathBuf::from("config.toml"),
+            table: r#"
+                [defaults]
+                color = "never"
+                strip = false
+                value = true
+
+                [profiles.ops]
+                value = true
+                summary = "52 35 11 55"
+                highlight = ["Symbol", "11=ABC"]
+
+                [profiles.raw]
+                value = false
+
+                [profiles.orders.summary]
+                D = "{Symbol}"
+                ExecutionReport = "{OrdStatus}"
+            "#
+            .parse()
+            .unwrap(),
+        };
+        let args =
+            |entries: Vec<Entry>| entries.into_iter().map(|(_, arg)| arg).collect::<Vec<_>>();
+        assert_eq!(
+            args(config.defaults().unwrap()),
+            ["--color=never", "--strip=false", "--value"]
+        );
+        assert_eq!(args(config.profile("raw").unwrap()), ["--value=false"]);
+        assert_eq!(
+            args(config.profile("ops").unwrap()),
+            [
+                "--highlight=Symbol",
+                "--highlight=11=ABC",
+                "--summary=52 35 11 55",
+                "--value"
+            ]
+        );
+        assert_eq!(
+            args(config.profile("orders").unwrap()),
+            [
+                "--summary=D={Symbol}",
+                "--summary=ExecutionReport={OrdStatus}"
+            ]
+        );
+        assert!(config.profile("dev").is_err());
+    }
+
+    #[test]
+    fn override_case() {
+        let command = crate::command::make_command();
+        let entry = |flag: &str, arg: &str| (flag.to_string(), arg.to_string());
+        let entries = vec![
+            entry("summary", "--summary=35"),
+            entry("value", "--value"),
+            entry("colour", "--colour=never"),
+            entry("delimiter", "--delimiter=|"),
+            entry("unknown", "--unknown"),
+        ];
+        let kept = without_overridden(&command, entries, &["stats", "value", "color"]);
+        assert_eq!(
+            kept,
+            [
+                entry("delimiter", "--delimiter=|"),
+                entry("unknown", "--unknown")
+            ]
+        );
+        // Conflicts are found from either side.
+        let entries = vec![entry("stats", "--stats=35"), entry("strip", "--strip")];
+        let kept = without_overridden(&command, entries, &["sessions"]);
+        assert_eq!(kept, [entry("strip", "--strip")]);
+        // A profile turning off a switch drops it from the defaults.
+        let defaults = vec![entry("value", "--value"), entry("strip", "--strip")];
+        let kept = without_overridden(&command, defaults, &["value"]);
+        assert_eq!(kept, [entry("strip", "--strip")]);
+        let matches = command
+            .try_get_matches_from(["prefix", "--strip", "--value=false"])
+            .unwrap();
+        assert!(!matches.get_flag("value"));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    format_to_summary,
+    tags::{MSG_TYPE, SENDER_COMP_ID, TARGET_COMP_ID},
+    value_name, Field, Printer,
+};
+use std::io::{self, Write};
+
+/// A language for sequence diagrams of the messages between CompIDs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Diagram {
+    Mermaid,
+    PlantUml,
+}
+
+impl Diagram {
+    /// Parses `mermaid` or `plantuml`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input.to_ascii_lowercase().as_str() {
+            "mermaid" => Ok(Diagram::Mermaid),
+            "plantuml" => Ok(Diagram::PlantUml),
+            _ => Err(format!(
+                "unknown diagram '{input}', expected mermaid or plantuml"
+            )),
+        }
+    }
+}
+
+// The CompIDs in the order they first appear, and an arrow for each message between them.
+#[derive(Debug, Default)]
+pub(crate) struct Flow {
+    participants: Vec<String>,
+    arrows: Vec<(usize, usize, String)>,
+}
+
+impl Flow {
+    fn participant(&mut self, comp_id: &str) -> usize {
+        match self.participants.iter().position(|p| p == comp_id) {
+            Some(i) => i,
+            None => {
+                self.participants.push(comp_id.to_string());
+                self.participants.len() - 1
+            }
+        }
+    }
+}
+
+impl Printer {
+    // Adds an arrow from the SenderCompID to the TargetCompID of a message, labelled with its
+    // MsgType and summary. Messages missing either CompID are left out.
+    pub(crate) fn add_arrow(&self, fix_msg: &[Field], line: &str) {
+        if !self.matches_filters(fix_msg, line) {
+            return;
+        }
+        let value = |tag| {
+            fix_msg
+                .iter()
+                .find(|field| field.tag == tag)
+                .map(|field| field.value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let (Some(sender), Some(target)) = (value(SENDER_COMP_ID), value(TARGET_COMP_ID)) else {
+            return;
+        };
+        let msg_type = value(MSG_TYPE).unwrap_or_default();
+        let mut label = value_name(MSG_TYPE, msg_type)
+            .unwrap_or(msg_type)
+            .to_string();
+        if let Some(summary) = format_to_summary(fix_msg, self.options()) {
+            label = format!("{label} {summary}").trim().to_string();
+        }
+        let mut flow = self.flow.lock().unwrap();
+        let (from, to) = (flow.participant(sender), flow.participant(target));
+        flow.arrows.push((from, to, label));
+    }
+
+    /// Prints a sequence diagram of the messages between each SenderCompID and TargetCompID, in the
+    /// language of [`Options::diagram`](crate::Options).
+    pub fn write_diagram(&self, out: &mut impl Write) -> io::Result<()> {
+        let Some(diagram) = self.options().diagram else {
+            return Ok(());
+        };
+        let flow = self.flow.lock().unwrap();
+        // Names that are not plain words are given an alias to keep the diagram valid.
+        let ids: Vec<String> = flow
+            .participants
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                if is_plain(name) {
+                    name.clone()
+                } else {
+                    format!("P{}", i + 1)
+                }
+            })
+            .collect();
+        match diagram {
+            Diagram::Mermaid => writeln!(out, "sequenceDiagram")?,
+            Diagram::PlantUml => writeln!(out, "@startuml")?,
+        }
+        for (id, name) in ids.iter().zip(&flow.participants) {
+            match diagram {
+                Diagram::Mermaid if id == name => writeln!(out, "    participant {id}")?,
+                Diagram::Mermaid => writeln!(out, "    participant {id} as {name}")?,
+                Diagram::PlantUml if id == name => writeln!(out, "participant {id}")?,
+                Diagram::PlantUml => writeln!(out, "participant \"{name}\" as {id}")?,
+            }
+        }
+        for (from, to, label) in &flow.arrows {
+            let (from, to) = (&ids[*from], &ids[*to]);
+            match diagram {
+                // Mermaid ends a message at a ; and reads # as the start of an entity code.
+                Diagram::Mermaid => {
+                    let label: String = label
+                        .chars()
+                        .map(|c| match c {
+                            '#' => String::from("#35;"),
+                            ';' => String::from("#59;"),
+                            c => c.to_string(),
+                        })
+                        .collect();
+                    writeln!(out, "    {from}->>{to}: {label}")?
+                }
+                Diagram::PlantUml => writeln!(out, "{from} -> {to} : {label}")?,
+            }
+        }
+        if diagram == Diagram::PlantUml {
+            writeln!(out, "@enduml")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_plain(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.eq_ignore_ascii_case("end")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_with, Options, Template};
+
+    #[test]
+    fn diagram_case() {
+        let input = "8=FIX.4.4|35=D|49=OMS|56=EUR-DESK|11=1;#|10=000|\n\
+                     8=FIX.4.4|35=8|49=EUR-DESK|56=OMS|11=1;#|10=000|\n\
+                     8=FIX.4.4|35=0|10=000|\n";
+        let draw = |diagram| {
+            let options = Options {
+                summary: Some(Template::parse("{ClOrdID}").unwrap()),
+                diagram: Some(diagram),
+                ..Options::default()
+            };
+            let printer = Printer::new(options);
+            let mut out = run_with(&printer, input.lines()).into_bytes();
+            printer.write_diagram(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            draw(Diagram::Mermaid),
+            "sequenceDiagram\n    \
+             participant OMS\n    \
+             participant P2 as EUR-DESK\n    \
+             OMS->>P2: NewOrderSingle 1#59;#35;\n    \
+             P2->>OMS: ExecutionReport 1#59;#35;\n"
+        );
+        assert_eq!(
+            draw(Diagram::PlantUml),
+            "@startuml\n\
+             participant OMS\n\
+             participant \"EUR-DESK\" as P2\n\
+             OMS -> P2 : NewOrderSingle 1;#\n\
+             P2 -> OMS : ExecutionReport 1;#\n\
+             @enduml\n"
+        );
+    }
+}
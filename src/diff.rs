@@ -0,0 +1,172 @@
+use crate::{tags, translate_value, write_colour, Field, Printer};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// A field that differs between two messages, fields are paired by their tag and which instance
+/// of that tag they are, so repeating groups are compared one instance at a time.
+#[derive(Debug, PartialEq)]
+pub struct FieldDiff<'a, 'b> {
+    pub tag: usize,
+    /// Starts from 0 for the first instance of a tag in the message.
+    pub instance: usize,
+    /// Missing when the field was added.
+    pub left: Option<&'b Field<'a>>,
+    /// Missing when the field was removed.
+    pub right: Option<&'b Field<'a>>,
+}
+
+/// Compares two messages field by field, in the order of the left message followed by any fields
+/// only found in the right message.
+pub fn diff<'a, 'b>(left: &'b [Field<'a>], right: &'b [Field<'a>]) -> Vec<FieldDiff<'a, 'b>> {
+    let left = with_instances(left);
+    let right = with_instances(right);
+    let mut result = Vec::new();
+    for &(instance, left_field) in &left {
+        let right_field = find(&right, left_field.tag, instance);
+        if right_field.is_none_or(|right_field| right_field.value != left_field.value) {
+            result.push(FieldDiff {
+                tag: left_field.tag,
+                instance,
+                left: Some(left_field),
+                right: right_field,
+            });
+        }
+    }
+    for &(instance, right_field) in &right {
+        if find(&left, right_field.tag, instance).is_none() {
+            result.push(FieldDiff {
+                tag: right_field.tag,
+                instance,
+                left: None,
+                right: Some(right_field),
+            });
+        }
+    }
+    result
+}
+
+fn with_instances<'a, 'b>(fields: &'b [Field<'a>]) -> Vec<(usize, &'b Field<'a>)> {
+    let mut counts = HashMap::new();
+    fields
+        .iter()
+        .map(|field| {
+            let count = counts.entry(field.tag).or_insert(0);
+            *count += 1;
+            (*count - 1, field)
+        })
+        .collect()
+}
+
+fn find<'a, 'b>(
+    fields: &[(usize, &'b Field<'a>)],
+    tag: usize,
+    instance: usize,
+) -> Option<&'b Field<'a>> {
+    fields
+        .iter()
+        .find(|(i, field)| field.tag == tag && *i == instance)
+        .map(|(_, field)| *field)
+}
+
+impl Printer {
+    /// Prints the fields that were removed, added or changed between two messages, returning
+    /// whether there were any differences.
+    pub fn write_diff(
+        &self,
+        out: &mut impl Write,
+        left: &[Field],
+        right: &[Field],
+    ) -> io::Result<bool> {
+        let diffs = diff(left, right);
+        self.write_field_diffs(out, &diffs, left, right, "")?;
+        Ok(!diffs.is_empty())
+    }
+
+    pub(crate) fn write_field_diffs(
+        &self,
+        out: &mut impl Write,
+        diffs: &[FieldDiff],
+        left: &[Field],
+        right: &[Field],
+        indent: &str,
+    ) -> io::Result<()> {
+        let flags = self.options();
+        let repeated = |tag| {
+            let count = |fields: &[Field]| fields.iter().filter(|f| f.tag == tag).count();
+            count(left) > 1 || count(right) > 1
+        };
+        let names: Vec<String> = diffs
+            .iter()
+            .map(|diff| {
+                let name = match tags::TAGS.get(diff.tag) {
+                    Some(tag) => Cow::Borrowed(*tag),
+                    None => Cow::Owned(diff.tag.to_string()),
+                };
+                if repeated(diff.tag) {
+                    format!("{name}[{}]", diff.instance + 1)
+                } else {
+                    name.into_owned()
+                }
+            })
+            .collect();
+        let width = names
+            .iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or_default();
+        let separator = if flags.strip { "=" } else { " = " };
+        for (diff, name) in diffs.iter().zip(&names) {
+            let (line, style) = match (diff.left, diff.right) {
+                (Some(left), Some(right)) => (
+                    format!(
+                        "~ {name:width$}{separator}{} -> {}",
+                        translate_value(left),
+                        translate_value(right)
+                    ),
+                    &flags.theme.changed,
+                ),
+                (Some(left), None) => (
+                    format!("- {name:width$}{separator}{}", translate_value(left)),
+                    &flags.theme.removed,
+                ),
+                (None, Some(right)) => (
+                    format!("+ {name:width$}{separator}{}", translate_value(right)),
+                    &flags.theme.added,
+                ),
+                (None, None) => unreachable!("a difference always has at least one field"),
+            };
+            out.write_all(indent.as_bytes())?;
+            write_colour(out, line, style, flags.colour)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fix_msg, FixMsg, Options, Printer};
+
+    #[test]
+    fn diff_case() {
+        let FixMsg::Partial(left) = parse_fix_msg("35=D|54=1|44=1.1|270=1.0|270=2.0") else {
+            panic!("Should be a partial FIX message");
+        };
+        let FixMsg::Partial(right) = parse_fix_msg("35=D|54=2|270=1.0|270=2.5|39=8") else {
+            panic!("Should be a partial FIX message");
+        };
+        let printer = Printer::new(Options::default());
+        let mut result = Vec::new();
+        assert!(printer.write_diff(&mut result, &left, &right).unwrap());
+        let expected = "~ Side         = Buy -> Sell\n\
+            - Price        = 1.1\n\
+            ~ MDEntryPx[2] = 2.0 -> 2.5\n\
+            + OrdStatus    = Rejected\n";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+        assert!(diff(&left, &left).is_empty());
+    }
+}
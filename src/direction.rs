@@ -0,0 +1,91 @@
+use crate::{
+    log_format::DIRECTION,
+    tags::{SENDER_COMP_ID, TARGET_COMP_ID},
+    write_colour, Field, Printer,
+};
+use std::io::{self, Write};
+
+impl Printer {
+    // Whether a message was sent, from comparing its CompIDs to ours or else the direction read by
+    // a log format. Returns None when neither says.
+    fn was_sent(&self, fix_msg: &[Field]) -> Option<bool> {
+        let value = |tag| {
+            fix_msg
+                .iter()
+                .find(|field| field.tag == tag)
+                .map(|field| field.value.trim())
+        };
+        if let Some(me) = &self.options().me {
+            if value(SENDER_COMP_ID) == Some(me) {
+                return Some(true);
+            }
+            if value(TARGET_COMP_ID) == Some(me) {
+                return Some(false);
+            }
+        }
+        match value(DIRECTION)? {
+            "Sent" => Some(true),
+            "Received" => Some(false),
+            _ => None,
+        }
+    }
+
+    // Writes an arrow for the direction of a message followed by the counterparty, which is its
+    // TargetCompID when sent and SenderCompID when received. Returns whether anything was written.
+    pub(crate) fn write_direction(
+        &self,
+        out: &mut impl Write,
+        fix_msg: &[Field],
+    ) -> io::Result<bool> {
+        let Some(sent) = self.was_sent(fix_msg) else {
+            return Ok(false);
+        };
+        let (arrow, tag) = if sent {
+            ('→', TARGET_COMP_ID)
+        } else {
+            ('←', SENDER_COMP_ID)
+        };
+        let counterparty = fix_msg
+            .iter()
+            .find(|field| field.tag == tag)
+            .map_or("", |field| field.value.trim());
+        let flags = self.options();
+        let label = format!("{arrow} {counterparty}");
+        write_colour(out, label.trim_end(), &flags.theme.header, flags.colour)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::run_lines, LogFormat, Options, Template};
+
+    #[test]
+    fn direction_case() {
+        let input = "8=FIX.4.4|35=D|49=OMS|56=VENUE|10=000|\n\
+                     8=FIX.4.4|35=8|49=VENUE|56=OMS|10=000|\n\
+                     OUT 8=FIX.4.4|35=0|49=OMS|56=VENUE|10=000|\n\
+                     8=FIX.4.4|35=0|49=A|56=B|10=000|\n";
+        let print = |options| run_lines(options, input.lines());
+        let options = Options {
+            summary: Some(Template::parse("35").unwrap()),
+            direction: true,
+            me: Some(String::from("OMS")),
+            ..Options::default()
+        };
+        assert_eq!(print(options), "→ VENUE  D\n← VENUE  8\n→ VENUE  0\n0\n");
+        let options = Options {
+            delimiter: String::from("|"),
+            direction: true,
+            log_formats: vec![LogFormat::parse("^(?P<direction>IN|OUT) ").unwrap()],
+            ..Options::default()
+        };
+        assert_eq!(
+            print(options),
+            "BeginString = FIX.4.4|MsgType = D|SenderCompID = OMS|TargetCompID = VENUE|CheckSum = 000|\n\
+             BeginString = FIX.4.4|MsgType = 8|SenderCompID = VENUE|TargetCompID = OMS|CheckSum = 000|\n\
+             → VENUE  Direction = Sent|BeginString = FIX.4.4|MsgType = 0|SenderCompID = OMS|TargetCompID = VENUE|CheckSum = 000|\n\
+             BeginString = FIX.4.4|MsgType = 0|SenderCompID = A|TargetCompID = B|CheckSum = 000|\n"
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use crate::{
+    parse_tag, parse_tag_number, tag_name,
+    time::{format_timestamp, parse_timestamp},
+    translate, Field,
+};
+use std::{borrow::Cow, fmt, iter::Peekable, str::CharIndices};
+
+// Computed fields are given tags after any real tag, so they can be looked up like other fields.
+pub(crate) const COMPUTED_TAG: usize = 1 << 30;
+
+/// A field computed from the other fields of a message, such as `Notional=OrderQty * Price`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Computed {
+    pub name: String,
+    pub expr: Expr,
+}
+
+impl Computed {
+    /// Parses `NAME=EXPRESSION`, the expression may use any of the computed fields given.
+    pub fn parse(input: &str, computed: &[Computed]) -> Result<Self, String> {
+        let (name, expr) = input
+            .split_once('=')
+            .ok_or_else(|| format!("'{input}' must be NAME=EXPRESSION"))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid name '{name}'"));
+        }
+        if name.starts_with(|c: char| c.is_ascii_digit()) || parse_tag(name).is_some() {
+            return Err(format!("'{name}' is already a tag"));
+        }
+        Ok(Computed {
+            name: name.to_string(),
+            expr: Expr::parse(expr, computed)?,
+        })
+    }
+}
+
+/// An expression over the fields of a message, used by computed fields and filters.
+///
+/// Fields are written as tag names, `#NUMBER` for tags without a name, or the names of computed
+/// fields. Values are numbers, UTCTimestamps or text, and can be combined with `+ - * /`, compared
+/// with `== != < <= > >=` and joined with `&& || !`. Subtracting two timestamps gives the seconds
+/// between them, and text is compared to both the value of a field and its translation, so
+/// `Side == "Buy"` works the same as `Side == 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Text(String),
+    Field(usize),
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Number(f64),
+    // Nanoseconds since the unix epoch.
+    Time(i64),
+    Text(String),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => f.write_str(&format_number(*number)),
+            Value::Time(time) => f.write_str(&format_timestamp(*time)),
+            Value::Text(text) => f.write_str(text),
+            Value::Bool(bool) => write!(f, "{bool}"),
+        }
+    }
+}
+
+impl Value {
+    fn from_field(value: &str) -> Self {
+        let number = value
+            .starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'))
+            .then(|| value.parse().ok())
+            .flatten();
+        match (number, parse_timestamp(value)) {
+            (Some(number), _) => Value::Number(number),
+            (None, Some(time)) => Value::Time(time),
+            (None, None) => Value::Text(value.to_string()),
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        match self {
+            Value::Number(number) => *number != 0.0,
+            Value::Time(_) => true,
+            Value::Text(text) => !text.is_empty(),
+            Value::Bool(bool) => *bool,
+        }
+    }
+}
+
+// Rounds away the noise of floating point arithmetic, so 0.1 + 0.2 prints as 0.3.
+pub(crate) fn format_number(number: f64) -> String {
+    let formatted = format!("{number:.6}");
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    match formatted {
+        "-0" => String::from("0"),
+        formatted => formatted.to_string(),
+    }
+}
+
+/// Returns the number of a tag given as a number or a name, or the tag given to a computed field
+/// by its name, which takes precedence.
+pub fn parse_field(input: &str, computed: &[Computed]) -> Option<usize> {
+    computed
+        .iter()
+        .position(|computed| computed.name.eq_ignore_ascii_case(input))
+        .map(|i| COMPUTED_TAG + i)
+        .or_else(|| parse_tag(input))
+}
+
+pub(crate) fn field_name(tag: usize, computed: &[Computed]) -> String {
+    match tag.checked_sub(COMPUTED_TAG) {
+        Some(i) => computed
+            .get(i)
+            .map_or_else(|| tag.to_string(), |computed| computed.name.clone()),
+        None => tag_name(tag).map_or_else(|| tag.to_string(), str::to_string),
+    }
+}
+
+// Appends the computed fields that have a value for the message, so they can be found by their tag
+// like any other field.
+pub(crate) fn with_computed<'a, 'b>(
+    fix_msg: &'b [Field<'a>],
+    computed: &[Computed],
+) -> Cow<'b, [Field<'a>]> {
+    if computed.is_empty() {
+        return Cow::Borrowed(fix_msg);
+    }
+    let mut fields = fix_msg.to_vec();
+    for (i, field) in computed.iter().enumerate() {
+        if let Some(value) = field.expr.eval(fix_msg, computed) {
+            fields.push(Field {
+                tag: COMPUTED_TAG + i,
+                value: Cow::Owned(value.to_string()),
+            });
+        }
+    }
+    Cow::Owned(fields)
+}
+
+impl Expr {
+    pub fn parse(input: &str, computed: &[Computed]) -> Result<Self, String> {
+        let mut parser = Parser {
+            input,
+            chars: input.char_indices().peekable(),
+            computed,
+        };
+        let node = parser.or()?;
+        parser.skip_whitespace();
+        if let Some((i, _)) = parser.chars.peek() {
+            return Err(format!("unexpected '{}' in '{input}'", &input[*i..]));
+        }
+        Ok(Expr { node })
+    }
+
+    /// Whether the expression is true for a message, missing fields make it false.
+    pub fn matches(&self, fix_msg: &[Field], computed: &[Computed]) -> bool {
+        self.eval(fix_msg, computed)
+            .is_some_and(|value| value.is_true())
+    }
+
+    pub(crate) fn eval(&self, fix_msg: &[Field], computed: &[Computed]) -> Option<Value> {
+        eval(&self.node, fix_msg, computed)
+    }
+}
+
+fn eval(node: &Node, fix_msg: &[Field], computed: &[Computed]) -> Option<Value> {
+    match node {
+        Node::Number(number) => Some(Value::Number(*number)),
+        Node::Text(text) => Some(Value::Text(text.clone())),
+        Node::Field(tag) => match tag.checked_sub(COMPUTED_TAG) {
+            Some(i) => computed.get(i)?.expr.eval(fix_msg, computed),
+            None => {
+                let field = fix_msg.iter().find(|field| field.tag == *tag)?;
+                Some(Value::from_field(&field.value))
+            }
+        },
+        Node::Not(node) => {
+            let value = eval(node, fix_msg, computed);
+            Some(Value::Bool(!value.is_some_and(|value| value.is_true())))
+        }
+        Node::Negate(node) => match eval(node, fix_msg, computed)? {
+            Value::Number(number) => Some(Value::Number(-number)),
+            _ => None,
+        },
+        Node::Binary(Op::And, left, right) => {
+            let is_true = |node| eval(node, fix_msg, computed).is_some_and(|v| v.is_true());
+            Some(Value::Bool(is_true(left) && is_true(right)))
+        }
+        Node::Binary(Op::Or, left, right) => {
+            let is_true = |node| eval(node, fix_msg, computed).is_some_and(|v| v.is_true());
+            Some(Value::Bool(is_true(left) || is_true(right)))
+        }
+        Node::Binary(op @ (Op::Equal | Op::NotEqual), left, right) => {
+            let equal = equal(left, right, fix_msg, computed)?;
+            Some(Value::Bool(equal == (*op == Op::Equal)))
+        }
+        Node::Binary(op, left, right) => {
+            let left = eval(left, fix_msg, computed)?;
+            let right = eval(right, fix_msg, computed)?;
+            binary(*op, left, right)
+        }
+    }
+}
+
+// Text compares to the value of a field or its translation, anything else compares by value.
+fn equal(left: &Node, right: &Node, fix_msg: &[Field], computed: &[Computed]) -> Option<bool> {
+    let texts = |node: &Node| -> Option<Vec<String>> {
+        let value = eval(node, fix_msg, computed)?;
+        let mut texts = vec![value.to_string()];
+        if let Node::Field(tag) = node {
+            if let Some(field) = fix_msg.iter().find(|field| field.tag == *tag) {
+                texts.push(field.value.to_string());
+                texts.extend(translate(*tag, &field.value).map(str::to_string));
+            }
+        }
+        Some(texts)
+    };
+    let (left_value, right_value) = (
+        eval(left, fix_msg, computed)?,
+        eval(right, fix_msg, computed)?,
+    );
+    if matches!(left_value, Value::Text(_)) || matches!(right_value, Value::Text(_)) {
+        let (left, right) = (texts(left)?, texts(right)?);
+        return Some(
+            left.iter()
+                .any(|left| right.iter().any(|right| left.eq_ignore_ascii_case(right))),
+        );
+    }
+    Some(left_value == right_value)
+}
+
+fn binary(op: Op, left: Value, right: Value) -> Option<Value> {
+    use Value::{Number, Text, Time};
+    let value = match (op, left, right) {
+        (Op::Add, Number(a), Number(b)) => Number(a + b),
+        (Op::Subtract, Number(a), Number(b)) => Number(a - b),
+        (Op::Multiply, Number(a), Number(b)) => Number(a * b),
+        (Op::Divide, Number(_), Number(0.0)) => return None,
+        (Op::Divide, Number(a), Number(b)) => Number(a / b),
+        (Op::Subtract, Time(a), Time(b)) => Number((a - b) as f64 / 1e9),
+        (Op::Add, Time(a), Number(b)) => Time(a + (b * 1e9) as i64),
+        (Op::Subtract, Time(a), Number(b)) => Time(a - (b * 1e9) as i64),
+        (op, left, right) => {
+            let ordering = match (left, right) {
+                (Number(a), Number(b)) => a.partial_cmp(&b)?,
+                (Time(a), Time(b)) => a.cmp(&b),
+                (Text(a), Text(b)) => a.cmp(&b),
+                _ => return None,
+            };
+            Value::Bool(match op {
+                Op::Less => ordering.is_lt(),
+                Op::LessEqual => ordering.is_le(),
+                Op::Greater => ordering.is_gt(),
+                Op::GreaterEqual => ordering.is_ge(),
+                _ => return None,
+            })
+        }
+    };
+    Some(value)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    computed: &'a [Computed],
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    // Consumes the operator if it is next.
+    fn eat(&mut self, op: &str) -> bool {
+        self.skip_whitespace();
+        let Some(&(i, _)) = self.chars.peek() else {
+            return false;
+        };
+        if !self.input[i..].starts_with(op) {
+            return false;
+        }
+        for _ in op.chars() {
+            self.chars.next();
+        }
+        true
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, Op)],
+        next: fn(&mut Self) -> Result<Node, String>,
+    ) -> Result<Node, String> {
+        let mut left = next(self)?;
+        'outer: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    left = Node::Binary(*op, Box::new(left), Box::new(next(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        self.binary(&[("||", Op::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        self.binary(&[("&&", Op::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Node, String> {
+        // Longer operators first, so <= is not read as <.
+        let ops = [
+            ("==", Op::Equal),
+            ("!=", Op::NotEqual),
+            ("<=", Op::LessEqual),
+            (">=", Op::GreaterEqual),
+            ("<", Op::Less),
+            (">", Op::Greater),
+        ];
+        self.binary(&ops, Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        let ops = [("+", Op::Add), ("-", Op::Subtract), ("−", Op::Subtract)];
+        self.binary(&ops, Self::product)
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        let ops = [
+            ("*", Op::Multiply),
+            ("×", Op::Multiply),
+            ("/", Op::Divide),
+            ("÷", Op::Divide),
+        ];
+        self.binary(&ops, Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat("!") {
+            Ok(Node::Not(Box::new(self.unary()?)))
+        } else if self.eat("-") || self.eat("−") {
+            Ok(Node::Negate(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        self.skip_whitespace();
+        let Some(&(start, c)) = self.chars.peek() else {
+            return Err(format!("'{}' ends too early", self.input));
+        };
+        if self.eat("(") {
+            let node = self.or()?;
+            if !self.eat(")") {
+                return Err(format!("missing ')' in '{}'", self.input));
+            }
+            return Ok(node);
+        }
+        if c == '"' {
+            self.chars.next();
+            let mut text = String::new();
+            loop {
+                match self.chars.next() {
+                    Some((_, '"')) => return Ok(Node::Text(text)),
+                    Some((_, '\\')) => text.extend(self.chars.next().map(|(_, c)| c)),
+                    Some((_, c)) => text.push(c),
+                    None => return Err(format!("unclosed '\"' in '{}'", self.input)),
+                }
+            }
+        }
+        let word = |parser: &mut Self, start| {
+            let mut end = start;
+            while let Some((i, c)) = parser
+                .chars
+                .next_if(|(_, c)| c.is_ascii_alphanumeric() || matches!(c, '_' | '.'))
+            {
+                end = i + c.len_utf8();
+            }
+            &parser.input[start..end]
+        };
+        if c == '#' {
+            self.chars.next();
+            let number = word(self, start + 1);
+            return parse_tag_number(number)
+                .map(Node::Field)
+                .ok_or_else(|| format!("invalid tag number '#{number}'"));
+        }
+        let word = word(self, start);
+        if word.is_empty() {
+            return Err(format!(
+                "unexpected '{}' in '{}'",
+                &self.input[start..],
+                self.input
+            ));
+        }
+        if word.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return word
+                .parse()
+                .map(Node::Number)
+                .map_err(|_| format!("invalid number '{word}'"));
+        }
+        parse_field(word, self.computed)
+            .map(Node::Field)
+            .ok_or_else(|| format!("unknown tag '{word}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_lines, Options, Template};
+
+    #[test]
+    fn computed_case() {
+        let mut computed = vec![Computed::parse("Notional=OrderQty × Price", &[]).unwrap()];
+        for definition in [
+            "Latency=(SendingTime - TransactTime) * 1000",
+            "Thousands=Notional / 1000",
+        ] {
+            computed.push(Computed::parse(definition, &computed).unwrap());
+        }
+        assert!(Computed::parse("Side=1", &[]).is_err());
+        assert!(Expr::parse("OrderQty +", &[]).is_err());
+        assert!(Expr::parse("#1073741824 > 0", &computed).is_err());
+        assert!(Template::parse_with("{1073741824}", &computed).is_err());
+        assert!(Expr::parse("Foo > 1", &[]).is_err());
+
+        let options = Options {
+            summary: Some(
+                Template::parse_with(
+                    "{Symbol} {Notional:,.2} {Latency}ms {Thousands}k",
+                    &computed,
+                )
+                .unwrap(),
+            ),
+            filters: vec![Expr::parse(r#"Side == "Buy" && Notional >= 1000"#, &computed).unwrap()],
+            computed,
+            ..Options::default()
+        };
+        let lines = [
+            "35=D|52=20240101-10:00:00.250|60=20240101-10:00:00|54=1|55=VOD|38=1000|44=12.5",
+            "35=D|52=20240101-10:00:01|60=20240101-10:00:00|54=2|55=BP|38=10|44=400",
+            "35=D|54=1|55=BARC|38=1|44=200",
+        ];
+        assert_eq!(run_lines(options, lines), "VOD 12,500.00 250ms 12.5k\n");
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{stats::Stats, Output, Printer};
+use memchr::memchr;
+use memmap2::Mmap;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    thread,
+};
+
+// Each chunk is extended to the end of its last line so no line is split between threads.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+impl Printer {
+    /// Prints every line of a file, regular files are mapped into memory and split into chunks
+    /// that are parsed in parallel, while keeping the output in the same order as the input.
+    pub fn run_file(&self, out: &mut Output<impl Write>, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::open(path)?;
+        if !file.metadata()?.is_file() {
+            // Pipes and devices can't be mapped, so read them a line at a time instead.
+            return self.run_reader(out, BufReader::new(file));
+        }
+        // SAFETY: The map is only ever read. If the file is truncated by another process while it
+        // is being read prefix may be killed, the same as any other tool that maps its input.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let chunks = split_lines(&mmap, CHUNK_SIZE);
+        let threads = if self.is_sequential() {
+            1
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        };
+
+        // Chunks are handled a batch at a time and their output written in order, this keeps the
+        // output identical to reading the file line by line without holding all of it in memory.
+        for batch in chunks.chunks(threads) {
+            let outputs = thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            let mut output =
+                                Output::new(Vec::with_capacity(chunk.len() * 2), false);
+                            self.run_chunk(&mut output, chunk).map(|_| output)
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            for output in outputs {
+                out.append(output?)?;
+            }
+            if out.line_buffered() {
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints every line read from the reader, flushing the output whenever the reader runs out of
+    /// buffered input so the output keeps up with a log that is still being written.
+    pub fn run_reader<R: Read>(
+        &self,
+        out: &mut Output<impl Write>,
+        mut reader: BufReader<R>,
+    ) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        loop {
+            let Some(input) = read_line(&mut reader, &mut buffer)? else {
+                return Ok(());
+            };
+            self.run(out, &input)?;
+            // Nothing left in the buffer means the next read may block, such as when following a
+            // log, so make sure everything parsed so far has been printed.
+            if out.line_buffered() || reader.buffer().is_empty() {
+                out.flush()?;
+            }
+        }
+    }
+
+    fn run_chunk(&self, out: &mut Output<impl Write>, chunk: &[u8]) -> io::Result<()> {
+        let text = String::from_utf8_lossy(chunk);
+        if !self.options().stats.is_empty() {
+            // Count into stats of its own so threads only share them once the chunk is done.
+            let mut stats = Stats::default();
+            for line in text.lines() {
+                self.count_line(&mut stats, line);
+            }
+            self.stats.lock().unwrap().merge(stats);
+            return Ok(());
+        }
+        for line in text.lines() {
+            self.run(out, line)?;
+        }
+        Ok(())
+    }
+}
+
+// Reads a line without its line ending. Invalid UTF-8 is replaced the same as in mapped files, so
+// a file gives the same output however it is read.
+pub(crate) fn read_line<'a>(
+    reader: &mut impl BufRead,
+    buffer: &'a mut Vec<u8>,
+) -> io::Result<Option<Cow<'a, str>>> {
+    buffer.clear();
+    if reader.read_until(b'\n', buffer)? == 0 {
+        return Ok(None);
+    }
+    let mut line: &[u8] = buffer;
+    if let Some(rest) = line.strip_suffix(b"\n") {
+        line = rest.strip_suffix(b"\r").unwrap_or(rest);
+    }
+    Ok(Some(String::from_utf8_lossy(line)))
+}
+
+fn split_lines(input: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let end = (start + chunk_size).min(input.len());
+        let end = memchr(b'\n', &input[end..]).map_or(input.len(), |i| end + i + 1);
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, Output, Printer};
+
+    #[test]
+    fn file_case() {
+        let input = b"8=FIX.4.4|35=D|58=caf\xe9|10=000|\r\nnot \xff fix\n";
+        let path = std::env::temp_dir().join(format!("prefix-file-{}.log", std::process::id()));
+        std::fs::write(&path, input).unwrap();
+        let printer = Printer::new(Options::default());
+        let mut mapped = Output::new(Vec::new(), false);
+        printer.run_file(&mut mapped, &path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let mut read = Output::new(Vec::new(), false);
+        printer
+            .run_reader(&mut read, io::BufReader::new(&input[..]))
+            .unwrap();
+        let expected = "BeginString = FIX.4.4\nMsgType = D\nText = caf\u{fffd}\nCheckSum = 000\n\n\
+                        not \u{fffd} fix\n";
+        assert_eq!(String::from_utf8(mapped.into_inner()).unwrap(), expected);
+        assert_eq!(String::from_utf8(read.into_inner()).unwrap(), expected);
+    }
+}
@@ -0,0 +1,795 @@
+//! Parsing and pretty printing of FIX messages, the library behind the prefix command line tool.
+//!
+//! ```
+//! use prefix::{parse_fix_msg, FixMsg, Options, Printer};
+//!
+//! let options = Options {
+//!     delimiter: String::from("|"),
+//!     value: true,
+//!     ..Options::default()
+//! };
+//! let printer = Printer::new(options);
+//! let FixMsg::Partial(fields) = parse_fix_msg("35=D|54=1|55=EUR/USD") else {
+//!     panic!("should be a partial FIX message");
+//! };
+//! let mut result = Vec::new();
+//! printer.write_fix_msg(&mut result, &fields).unwrap();
+//! assert_eq!(result, b"MsgType = NewOrderSingle|Side = Buy|Symbol = EUR/USD|");
+//! ```
+
+mod changes;
+mod diagram;
+mod diff;
+mod direction;
+mod expr;
+mod file;
+mod log_format;
+mod merge;
+mod output;
+mod parser;
+mod reconcile;
+mod reject;
+mod session;
+mod split;
+mod stats;
+mod tags;
+mod template;
+mod theme;
+mod time;
+mod zone;
+
+pub use diagram::Diagram;
+pub use diff::{diff, FieldDiff};
+pub use expr::{parse_field, Computed, Expr};
+pub use log_format::LogFormat;
+pub use output::Output;
+pub use parser::{fields, parse_fix_msg, Field, Fields, FixMsg};
+pub use reconcile::{is_fill, read_fills, reconcile, Reconciliation};
+use regex::Regex;
+pub use stats::Aggregate;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+pub use template::Template;
+pub use theme::{Highlight, Rule, Theme};
+pub use time::{TimeBound, TimeFormat, TimeSource};
+pub use zone::TimeZone;
+
+/// Options controlling how messages are printed, the defaults match running prefix without any
+/// flags on a stdout that is not a terminal. Fields may be added in later versions, so build them
+/// with `..Options::default()`.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Printed after each field.
+    pub delimiter: String,
+    /// Colour each field using the theme.
+    pub colour: bool,
+    pub theme: Theme,
+    /// Drop lines that do not contain a FIX message.
+    pub only_fix: bool,
+    /// Combine repeating groups into a single field with a comma delimited value.
+    pub repeating: bool,
+    /// Only treat messages containing both BeginString and CheckSum as FIX messages.
+    pub strict: bool,
+    /// Strip the whitespace around the `=` in each field.
+    pub strip: bool,
+    /// Summarise each message with a template instead of printing every field.
+    pub summary: Option<Template>,
+    /// Templates for messages of a MsgType, used instead of `summary`. Messages of other types
+    /// without a `summary` to fall back to are printed in full.
+    pub summaries: HashMap<String, Template>,
+    /// Translate tag numbers on lines that are not FIX messages.
+    pub tag: bool,
+    /// Translate the values of enumerated fields, for Side: 1 -> Buy.
+    pub value: bool,
+    /// Fields to mark with the highlight style of the theme when colour is used.
+    pub highlights: Vec<Highlight>,
+    /// Only print the fields that changed since the last message with the same ClOrdID or OrderID.
+    pub changes: bool,
+    /// Count the messages for each value of these tags instead of printing them, the results
+    /// are printed by [`Printer::write_stats`].
+    pub stats: Vec<usize>,
+    /// Aggregates to compute for each group of messages counted for `stats`.
+    pub aggregates: Vec<Aggregate>,
+    /// Print stats as CSV instead of a table.
+    pub csv: bool,
+    /// Follow the liveness of each session instead of printing messages, the report is printed
+    /// by [`Printer::write_sessions`].
+    pub sessions: bool,
+    /// Print the message each Reject or BusinessMessageReject refers to by its RefSeqNum under it.
+    pub resolve_rejects: bool,
+    /// Print → for each message that was sent or ← for each that was received before it, followed
+    /// by the counterparty, taken from `me` or else the Direction read by `log_formats`.
+    pub direction: bool,
+    /// Our CompID, messages with it as their SenderCompID were sent and as their TargetCompID were
+    /// received.
+    pub me: Option<String>,
+    /// Collect the messages into a sequence diagram between CompIDs instead of printing them, the
+    /// diagram is printed by [`Printer::write_diagram`].
+    pub diagram: Option<Diagram>,
+    /// Fields computed from the others, which templates, stats and filters can use by name.
+    pub computed: Vec<Computed>,
+    /// Only print or count the FIX messages for which every expression is true.
+    pub filters: Vec<Expr>,
+    /// How to print UTCTimestamp and UTCTimeOnly fields.
+    pub time: TimeFormat,
+    /// Only print or count the FIX messages at or after this time.
+    pub since: Option<TimeBound>,
+    /// Only print or count the FIX messages at or before this time.
+    pub until: Option<TimeBound>,
+    /// Where the time compared to `since` and `until` comes from.
+    pub time_source: TimeSource,
+    /// Formats of the text logged before each message, the first to match adds the LogTime,
+    /// Direction and Session it logged to the message.
+    pub log_formats: Vec<LogFormat>,
+    /// Write each message to a file in `out_dir` named after its values of these tags instead of
+    /// printing it, the files are flushed by [`Printer::flush_split`].
+    pub split_by: Vec<usize>,
+    pub out_dir: PathBuf,
+    /// Write the line each message was read from to the split files instead of printing it.
+    pub raw: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            delimiter: String::from("\n"),
+            colour: false,
+            theme: Theme::default(),
+            only_fix: false,
+            repeating: false,
+            strict: false,
+            strip: false,
+            summary: None,
+            summaries: HashMap::new(),
+            tag: false,
+            value: false,
+            highlights: Vec::new(),
+            changes: false,
+            stats: Vec::new(),
+            aggregates: Vec::new(),
+            csv: false,
+            sessions: false,
+            resolve_rejects: false,
+            direction: false,
+            me: None,
+            diagram: None,
+            computed: Vec::new(),
+            filters: Vec::new(),
+            time: TimeFormat::Utc,
+            since: None,
+            until: None,
+            time_source: TimeSource::default(),
+            log_formats: Vec::new(),
+            split_by: Vec::new(),
+            out_dir: PathBuf::from("."),
+            raw: false,
+        }
+    }
+}
+
+/// Prints FIX messages according to a set of [`Options`], the regexes needed for them are only
+/// compiled once so a printer should be reused for every line.
+pub struct Printer {
+    options: Options,
+    tag_regex: Regex,
+    orders: Mutex<changes::Orders>,
+    stats: Mutex<stats::Stats>,
+    sessions: Mutex<session::Sessions>,
+    messages: Mutex<reject::Messages>,
+    files: Mutex<split::Files>,
+    flow: Mutex<diagram::Flow>,
+    // The date of the first message, for times of day.
+    log_date: OnceLock<i64>,
+}
+
+impl Printer {
+    pub fn new(options: Options) -> Self {
+        Printer {
+            tag_regex: get_tag_regex(),
+            orders: Mutex::default(),
+            stats: Mutex::default(),
+            sessions: Mutex::default(),
+            messages: Mutex::default(),
+            files: Mutex::default(),
+            flow: Mutex::default(),
+            log_date: OnceLock::new(),
+            options,
+        }
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    // Some options depend on earlier messages, so every line has to be handled in order.
+    pub(crate) fn is_sequential(&self) -> bool {
+        self.options.changes
+            || self.options.sessions
+            || !self.options.split_by.is_empty()
+            || self.options.diagram.is_some()
+            || self.options.resolve_rejects
+            || matches!(self.options.time, TimeFormat::Relative(_))
+            || [self.options.since, self.options.until]
+                .iter()
+                .any(|bound| matches!(bound, Some(TimeBound::TimeOfDay(_))))
+    }
+
+    /// Parses a single line and prints it the same way as the prefix command line tool, lines
+    /// without a FIX message are passed through unless [`Options::only_fix`] is set. With
+    /// [`Options::stats`] or [`Options::sessions`] set the message is only counted, with
+    /// [`Options::split_by`] it is written to its file instead, and with [`Options::diagram`] it
+    /// is added to the diagram.
+    pub fn run(&self, out: &mut Output<impl Write>, input: &str) -> io::Result<()> {
+        let flags = &self.options;
+        if !flags.stats.is_empty() {
+            self.count_line(&mut self.stats.lock().unwrap(), input);
+            return Ok(());
+        }
+        if !flags.split_by.is_empty() {
+            return match self.parse_line(input) {
+                FixMsg::Full(parsed) => self.write_split(&parsed, input),
+                FixMsg::Partial(parsed) if !flags.strict => self.write_split(&parsed, input),
+                _ => Ok(()),
+            };
+        }
+        if flags.diagram.is_some() {
+            match self.parse_line(input) {
+                FixMsg::Full(parsed) => self.add_arrow(&parsed, input),
+                FixMsg::Partial(parsed) if !flags.strict => self.add_arrow(&parsed, input),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if flags.sessions {
+            match self.parse_line(input) {
+                FixMsg::Full(parsed) if self.matches_filters(&parsed, input) => {
+                    self.sessions.lock().unwrap().add(&parsed)
+                }
+                FixMsg::Partial(parsed)
+                    if !flags.strict && self.matches_filters(&parsed, input) =>
+                {
+                    self.sessions.lock().unwrap().add(&parsed)
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        match self.parse_line(input) {
+            FixMsg::Full(parsed) => self.print_fix_msg(out, &parsed, input),
+            FixMsg::Partial(parsed) => {
+                if !flags.strict {
+                    self.print_fix_msg(out, &parsed, input)
+                } else if !flags.only_fix {
+                    print_non_fix_msg(out, input, &self.tag_regex, flags)
+                } else {
+                    Ok(())
+                }
+            }
+            FixMsg::None => {
+                if !flags.only_fix {
+                    print_non_fix_msg(out, input, &self.tag_regex, flags)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Writes every field of a message followed by the delimiter.
+    pub fn write_fix_msg(&self, out: &mut impl Write, fix_msg: &[Field]) -> io::Result<()> {
+        write_fix_msg(out, fix_msg, &self.options)
+    }
+
+    /// Fills in the [`Options::summaries`] template for the MsgType of a message, or the
+    /// [`Options::summary`] template, if there is one.
+    pub fn summarise(&self, fix_msg: &[Field]) -> Option<String> {
+        format_to_summary(fix_msg, &self.options)
+    }
+
+    // The line is the whole of the input, for the time logged before the message.
+    pub(crate) fn matches_filters(&self, fix_msg: &[Field], line: &str) -> bool {
+        let flags = &self.options;
+        self.in_time_range(fix_msg, line)
+            && flags
+                .filters
+                .iter()
+                .all(|filter| filter.matches(fix_msg, &flags.computed))
+    }
+
+    fn print_fix_msg(
+        &self,
+        out: &mut Output<impl Write>,
+        fix_msg: &[Field],
+        line: &str,
+    ) -> io::Result<()> {
+        if !self.matches_filters(fix_msg, line) {
+            // A reject can still refer to a message that was filtered out.
+            if self.options.resolve_rejects {
+                self.messages.lock().unwrap().insert(fix_msg);
+            }
+            return Ok(());
+        }
+        if let Some(summary) = self.summarise(fix_msg) {
+            let flags = &self.options;
+            // Summaries have no header, so messages matching a header rule colour the whole line.
+            let style = match flags.theme.header_rule_style(fix_msg) {
+                Some(style) if flags.colour => style,
+                _ => "",
+            };
+            if flags.direction && self.write_direction(out, fix_msg)? {
+                write!(out, "  ")?;
+            }
+            write_colour(out, summary, style, flags.colour)?;
+            writeln!(out)?;
+            if flags.resolve_rejects {
+                self.write_referenced(out, fix_msg)?;
+            }
+            return Ok(());
+        }
+        if self.options.direction && self.write_direction(out, fix_msg)? {
+            match self.options.delimiter.as_str() {
+                "\n" => writeln!(out)?,
+                _ => write!(out, "  ")?,
+            }
+        }
+        if self.options.changes {
+            self.write_fix_msg(out, &self.changed_fields(fix_msg))?;
+        } else {
+            self.write_fix_msg(out, fix_msg)?;
+        }
+        if self.options.delimiter != "\n" {
+            writeln!(out)?;
+        }
+        if self.options.resolve_rejects {
+            self.write_referenced(out, fix_msg)?;
+        }
+        // Avoid adding an empty new line at the bottom of the output.
+        if self.options.delimiter == "\n" {
+            out.defer_newline();
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of a tag from the FIX 4.4 dictionary.
+pub fn tag_name(tag: usize) -> Option<&'static str> {
+    tags::TAGS
+        .get(tag)
+        .copied()
+        .filter(|name| !name.is_empty())
+        .or_else(|| log_format::field_name(tag))
+}
+
+/// Returns the number of a tag from its name, ignoring case. The LogTime, Direction and Session
+/// added by [`Options::log_formats`] have tags of their own.
+pub fn tag_number(name: &str) -> Option<usize> {
+    tags::TAGS
+        .iter()
+        .position(|tag_name| eq_ignore_ascii_case(name, tag_name) && !tag_name.is_empty())
+        .or_else(|| log_format::field_tag(name))
+}
+
+/// Returns the number of a tag given as either a number or a name, such as `55` or `Symbol`.
+pub fn parse_tag(input: &str) -> Option<usize> {
+    parse_tag_number(input).or_else(|| tag_number(input))
+}
+
+// The numbers from LOG_TIME up are the tags of log and computed fields, which are only given by
+// name.
+pub(crate) fn parse_tag_number(input: &str) -> Option<usize> {
+    input.parse().ok().filter(|&tag| tag < log_format::LOG_TIME)
+}
+
+/// Returns the name of an enumerated value, for Side (54): "1" -> "Buy".
+pub fn value_name(tag: usize, value: &str) -> Option<&'static str> {
+    tags::VALUES.get(format!("{tag}-{value}").as_str()).copied()
+}
+
+fn get_tag_regex() -> Regex {
+    Regex::new(r"[0-9]+").unwrap()
+}
+
+fn print_non_fix_msg(
+    out: &mut impl Write,
+    line: &str,
+    tag_regex: &Regex,
+    flags: &Options,
+) -> io::Result<()> {
+    if flags.tag {
+        writeln!(out, "{}", parse_tags(line, tag_regex))
+    } else {
+        writeln!(out, "{line}")
+    }
+}
+
+fn eq_ignore_ascii_case(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .all(|(ac, bc)| ac.eq_ignore_ascii_case(&bc))
+}
+
+fn parse_tags(input: &str, regex: &Regex) -> String {
+    if input.chars().all(|c| !c.is_ascii_digit()) {
+        if let Some(tag) = tag_number(input) {
+            return tag.to_string();
+        }
+    }
+    let mut result = input.to_owned();
+    for m in regex.find_iter(input) {
+        let tag = m.as_str().parse::<usize>().unwrap();
+        result = result.replace(m.as_str(), tags::TAGS.get(tag).unwrap_or(&m.as_str()));
+    }
+    result
+}
+
+fn write_colour(
+    out: &mut impl Write,
+    input: impl fmt::Display,
+    style: &str,
+    use_colour: bool,
+) -> io::Result<()> {
+    if use_colour && !style.is_empty() {
+        write!(out, "\x1b[{style}m{input}\x1b[0m")
+    } else {
+        write!(out, "{input}")
+    }
+}
+
+fn write_fix_msg(out: &mut impl Write, input: &[Field], flags: &Options) -> io::Result<()> {
+    let fix_msg = if flags.repeating {
+        &combine_repeating_groups(input)
+    } else {
+        input
+    };
+    let theme = &flags.theme;
+    let header_rule = if flags.colour {
+        theme.header_rule_style(input)
+    } else {
+        None
+    };
+    let separator = if flags.strip { "=" } else { " = " };
+    for field in fix_msg {
+        let value = display_value(field, input, flags);
+        // Allow custom tags to still be printed without translation
+        let tag = match tags::TAGS.get(field.tag).copied() {
+            Some(tag) => Cow::Borrowed(tag),
+            None if log_format::is_log_field(field.tag) => {
+                Cow::Borrowed(log_format::field_name(field.tag).unwrap())
+            }
+            None => Cow::Owned(field.tag.to_string()),
+        };
+        if flags.colour && is_highlighted(field, input, flags) {
+            // Mark the whole field, the same way grep marks a match.
+            let field = format!("{tag}{separator}{value}");
+            write_colour(out, field, &theme.highlight, flags.colour)?;
+        } else {
+            let tag_style = theme.tag_style(field.tag, header_rule);
+            write_colour(out, tag, tag_style, flags.colour)?;
+            write_colour(out, separator, &theme.separator, flags.colour)?;
+            let value_style = theme.value_style(field, value != field.value, header_rule);
+            write_colour(out, value, value_style, flags.colour)?;
+        }
+        write_colour(out, &flags.delimiter, &theme.separator, flags.colour)?;
+    }
+    Ok(())
+}
+
+fn format_to_summary(input: &[Field], flags: &Options) -> Option<String> {
+    let msg_type = input.iter().find(|field| field.tag == 35);
+    let template = msg_type
+        .and_then(|msg_type| flags.summaries.get(msg_type.value.as_ref()))
+        .or(flags.summary.as_ref())?;
+    let input: &[Field] = &expr::with_computed(input, &flags.computed);
+    let fix_msg = if flags.repeating {
+        &combine_repeating_groups(input)
+    } else {
+        input
+    };
+    let lookup = |tag| {
+        let field = fix_msg.iter().find(|field| field.tag == tag)?;
+        let value = display_value(field, input, flags);
+        Some((value, flags.colour && is_highlighted(field, input, flags)))
+    };
+    Some(template.render(lookup, &flags.theme.highlight))
+}
+
+// The value of a field as it is printed, with repeating groups combined each instance is translated
+// or formatted on its own.
+fn display_value<'a>(field: &'a Field, fix_msg: &[Field], flags: &Options) -> Cow<'a, str> {
+    if let Some(time) = flags.time.format(field.tag, &field.value, fix_msg) {
+        return Cow::Owned(time);
+    }
+    if flags.repeating && field.value.contains(',') {
+        let times: Option<Vec<String>> = field
+            .value
+            .split(',')
+            .map(|value| flags.time.format(field.tag, value, fix_msg))
+            .collect();
+        if let Some(times) = times {
+            return Cow::Owned(times.join(","));
+        }
+    }
+    if !flags.value {
+        Cow::Borrowed(field.value.as_ref())
+    } else if flags.repeating {
+        Cow::Owned(translate_combined_values(field))
+    } else {
+        translate_value(field)
+    }
+}
+
+// With repeating groups combined, a field is highlighted if any of its instances match.
+fn is_highlighted(field: &Field, fix_msg: &[Field], flags: &Options) -> bool {
+    flags.highlights.iter().any(|highlight| {
+        if flags.repeating {
+            fix_msg
+                .iter()
+                .any(|f| f.tag == field.tag && highlight.matches(f))
+        } else {
+            highlight.matches(field)
+        }
+    })
+}
+
+// RefTagID and RefMsgType refer to other tags and message types, so they take their names.
+fn translate(tag: usize, value: &str) -> Option<&'static str> {
+    match tag {
+        371 => value.trim().parse().ok().and_then(tag_name),
+        372 => value_name(35, value),
+        tag => value_name(tag, value),
+    }
+}
+
+fn translate_value<'a>(field: &'a Field) -> Cow<'a, str> {
+    translate_tokens(field.tag, &field.value)
+}
+
+// Fields holding several values translate each of them, so ExecInst: "G 1" -> "AllOrNone NotHeld".
+fn translate_tokens(tag: usize, value: &str) -> Cow<'_, str> {
+    if let Some(name) = translate(tag, value) {
+        return Cow::Borrowed(name);
+    }
+    if !tags::MULTIPLE_VALUE.contains(&tag) || !value.contains(' ') {
+        return Cow::Borrowed(value);
+    }
+    let tokens: Vec<&str> = value
+        .split(' ')
+        .map(|token| translate(tag, token).unwrap_or(token))
+        .collect();
+    Cow::Owned(tokens.join(" "))
+}
+
+fn translate_combined_values(field: &Field) -> String {
+    let mut values = String::new();
+    for value in field.value.split(',') {
+        if !values.is_empty() {
+            values.push(',')
+        }
+        values.push_str(&translate_tokens(field.tag, value));
+    }
+    values
+}
+
+fn combine_repeating_groups<'a>(input: &[Field<'a>]) -> Vec<Field<'a>> {
+    let mut result = Vec::<Field>::new();
+    for field in input {
+        if let Some(existing_field) = result.iter_mut().find(|f| f.tag == field.tag) {
+            let value = existing_field.value.to_mut();
+            value.push(',');
+            value.push_str(&field.value);
+        } else {
+            result.push(field.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs each line through a printer for the options, returning everything that was printed.
+    pub(crate) fn run_lines<S: AsRef<str>>(
+        options: Options,
+        lines: impl IntoIterator<Item = S>,
+    ) -> String {
+        run_with(&Printer::new(options), lines)
+    }
+
+    // The same as run_lines, for a printer that is used again after the lines.
+    pub(crate) fn run_with<S: AsRef<str>>(
+        printer: &Printer,
+        lines: impl IntoIterator<Item = S>,
+    ) -> String {
+        let mut out = Output::new(Vec::new(), false);
+        for line in lines {
+            printer.run(&mut out, line.as_ref()).unwrap();
+        }
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    macro_rules! field {
+        ($($tag:literal,$value:literal),+) => {
+            $(
+                Field{
+                    tag: $tag,
+                    value: Cow::Borrowed($value),
+                }
+            ),+
+        }
+    }
+
+    #[test]
+    fn basic_parse_case() {
+        let input = "8=4.4|1=test|55=EUR/USD|10=123";
+        let result = parse_fix_msg(input);
+        let expected = FixMsg::Full(vec![
+            field!(8, "4.4"),
+            field!(1, "test"),
+            field!(55, "EUR/USD"),
+            field!(10, "123"),
+        ]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_case() {
+        let input =
+            "25=test|1=aaa|8=4.4|123=Capital|243:log[]efssdfkj39809|55=ETH-USD|101=55:05:22";
+        let result = parse_fix_msg(input);
+        let expected = FixMsg::Partial(vec![
+            field!(25, "test"),
+            field!(1, "aaa"),
+            field!(8, "4.4"),
+            field!(123, "Capital"),
+            field!(55, "ETH-USD"),
+            field!(101, "55:05:22"),
+        ]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn malformed_parse_case() {
+        let input = "=1|2=|a=3=x|log 44==5^55=A B\x0110=";
+        let result = parse_fix_msg(input);
+        let expected = FixMsg::Partial(vec![field!(3, "x"), field!(44, "=5"), field!(55, "A B")]);
+        assert_eq!(result, expected);
+        assert_eq!(parse_fix_msg("no fields = here|"), FixMsg::None);
+    }
+
+    #[test]
+    fn format_case() {
+        let input = "8=FIX.4.4|1=test|55=ETH/USD|54=1|29999=50";
+        let FixMsg::Partial(parsed) = parse_fix_msg(input) else {
+            panic!("Should be a partial FIX message");
+        };
+        let flags = Options::default();
+        let mut result = Vec::new();
+        write_fix_msg(&mut result, &parsed, &flags).unwrap();
+        let result = String::from_utf8(result).unwrap();
+        let expected = String::from(
+            "BeginString = FIX.4.4\nAccount = test\nSymbol = ETH/USD\nSide = 1\n29999 = 50\n",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[allow(clippy::useless_conversion)]
+    fn format_args_case() {
+        let input = "8=FIX.4.4|1=test|55=ETH/USD|54=1|29999=50";
+        let FixMsg::Partial(parsed) = parse_fix_msg(input) else {
+            panic!("Should be a partial FIX message");
+        };
+        let flags = Options {
+            delimiter: String::from("|"),
+            colour: true,
+            only_fix: true,
+            repeating: true,
+            strict: true,
+            strip: true,
+            tag: true,
+            value: true,
+            ..Options::default()
+        };
+        let mut result = Vec::new();
+        write_fix_msg(&mut result, &parsed, &flags).unwrap();
+        let result = String::from_utf8(result).unwrap();
+        let expected = String::from(
+            "BeginString=FIX.4.4|Account=test|Symbol=ETH/USD|Side=Buy|29999=50|"
+                .replace("|", "\x1b[33m|\x1b[0m")
+                .replace("=", "\x1b[33m=\x1b[0m"),
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn summary_case() {
+        let input = [
+            field!(8, "4.4"),
+            field!(35, "D"),
+            field!(55, "EUR/USD"),
+            field!(10, "123"),
+        ];
+        let flags = Options {
+            summary: Some(Template::parse("35 for 55").unwrap()),
+            value: true,
+            ..Options::default()
+        };
+
+        let result = format_to_summary(&input, &flags).unwrap();
+        let expected = String::from("NewOrderSingle for EUR/USD");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn tag_case() {
+        let input = "symbol? 55";
+        let parsed = parse_tags(input, &get_tag_regex());
+        assert_eq!(parsed, "symbol? Symbol");
+        let input = "54,11,8";
+        let parsed = parse_tags(input, &get_tag_regex());
+        assert_eq!(parsed, "Side,ClOrdID,BeginString");
+    }
+
+    #[test]
+    fn lookup_case() {
+        assert_eq!(tag_name(55), Some("Symbol"));
+        assert_eq!(tag_name(101), None);
+        assert_eq!(tag_name(20000), None);
+        assert_eq!(tag_number("clordid"), Some(11));
+        assert_eq!(tag_number(""), None);
+        assert_eq!(parse_tag("20000"), Some(20000));
+        assert_eq!(parse_tag("logtime"), Some(log_format::LOG_TIME));
+        assert_eq!(parse_tag("536870912"), None);
+        assert_eq!(parse_tag("1073741824"), None);
+        assert_eq!(value_name(54, "1"), Some("Buy"));
+        assert_eq!(value_name(55, "1"), None);
+    }
+
+    #[test]
+    fn repeating_case() {
+        let input = [
+            field!(8, "4.4"),
+            field!(55, "EUR/USD"),
+            field!(268, "3"),
+            field!(270, "1.1"),
+            field!(270, "1.2"),
+            field!(270, "1.3"),
+            field!(271, "1000"),
+            field!(271, "2500"),
+            field!(271, "5000"),
+            field!(10, "123"),
+        ];
+        let result = combine_repeating_groups(&input);
+        let expected = [
+            field!(8, "4.4"),
+            field!(55, "EUR/USD"),
+            field!(268, "3"),
+            field!(270, "1.1,1.2,1.3"),
+            field!(271, "1000,2500,5000"),
+            field!(10, "123"),
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn multiple_value_case() {
+        assert_eq!(translate_value(&field!(18, "G 1")), "AllOrNone NotHeld");
+        assert_eq!(translate_value(&field!(277, "A ZZ")), "Cash ZZ");
+        assert_eq!(translate_value(&field!(55, "EUR USD")), "EUR USD");
+        let combined = combine_repeating_groups(&[field!(18, "G 1"), field!(18, "M")]);
+        assert_eq!(
+            translate_combined_values(&combined[0]),
+            "AllOrNone NotHeld,MidPricePeg"
+        );
+    }
+}
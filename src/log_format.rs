@@ -0,0 +1,247 @@
+use crate::{
+    parse_fix_msg,
+    parser::TAG_LIMIT,
+    time::{find_time, format_date_time},
+    Field, FixMsg, Printer,
+};
+use regex::Regex;
+use std::borrow::Cow;
+
+// What a log says about each message is added to it as fields with tags after any real tag, and
+// before the tags of computed fields.
+pub(crate) const LOG_TIME: usize = TAG_LIMIT;
+pub(crate) const DIRECTION: usize = LOG_TIME + 1;
+pub(crate) const SESSION: usize = LOG_TIME + 2;
+const NAMES: [&str; 3] = ["LogTime", "Direction", "Session"];
+
+const TIMESTAMP: &str = r"\d{8}-\d{2}:\d{2}:\d{2}(?:\.\d+)?";
+
+// Each built-in format is the patterns of the logs its engine writes, tried in order.
+const BUILT_IN: [(&str, &[&str]); 3] = [
+    (
+        "quickfixj",
+        &[
+            // ScreenLog: <20240101-10:00:00, FIX.4.4:OMS->VENUE, incoming> (8=FIX.4.4|...)
+            r"^<(?P<time>{TIMESTAMP}), (?P<session>[^,]+), (?P<direction>incoming|outgoing)> \($",
+            // FileLog: 20240101-10:00:00.000: 8=FIX.4.4|...
+            r"^(?P<time>{TIMESTAMP}): $",
+        ],
+    ),
+    (
+        "quickfixn",
+        &[
+            // ScreenLog: <FIX.4.4:OMS->VENUE, incoming> (8=FIX.4.4|...)
+            r"^<(?:(?P<session>[^,]+), )?(?P<direction>incoming|outgoing)> \(?$",
+            // FileLog: 20240101-10:00:00.000 : 8=FIX.4.4|...
+            r"^(?P<time>{TIMESTAMP}) : $",
+        ],
+    ),
+    (
+        "onixs",
+        // 20240101-10:00:00.000000 [OMS-VENUE] Inbound: 8=FIX.4.4|...
+        &[
+            r"^(?P<time>{TIMESTAMP})\s+(?:\[(?P<session>[^\]]+)\]\s+)?(?P<direction>Inbound|Outbound|Received|Sent)\b",
+        ],
+    ),
+];
+
+/// The format of the text a FIX engine logs before each message. Its `time`, `direction` and
+/// `session` are added to the message as the LogTime, Direction and Session fields, which can be
+/// printed, used in templates and filters, and used as the time of the message.
+#[derive(Debug, Clone)]
+pub struct LogFormat {
+    patterns: Vec<Regex>,
+}
+
+impl LogFormat {
+    /// Parses the name of a built-in format, `quickfixj`, `quickfixn` or `onixs`, or a regex
+    /// capturing at least one of the groups `time`, `direction` and `session`. `auto` tries every
+    /// built-in format.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let built_in = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .map(|pattern| Regex::new(&pattern.replace("{TIMESTAMP}", TIMESTAMP)).unwrap())
+                .collect::<Vec<_>>()
+        };
+        if input.eq_ignore_ascii_case("auto") {
+            let patterns = BUILT_IN.iter().flat_map(|(_, patterns)| built_in(patterns));
+            return Ok(LogFormat {
+                patterns: patterns.collect(),
+            });
+        }
+        if let Some((_, patterns)) = BUILT_IN
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(input))
+        {
+            return Ok(LogFormat {
+                patterns: built_in(patterns),
+            });
+        }
+        let regex = Regex::new(input).map_err(|error| format!("invalid pattern: {error}"))?;
+        if !regex
+            .capture_names()
+            .flatten()
+            .any(|name| ["time", "direction", "session"].contains(&name))
+        {
+            return Err(format!(
+                "'{input}' must capture at least one of (?P<time>...), (?P<direction>...) or (?P<session>...)"
+            ));
+        }
+        Ok(LogFormat {
+            patterns: vec![regex],
+        })
+    }
+
+    // The fields for what the first matching pattern captures from the text before a message.
+    fn fields<'a>(&self, prefix: &'a str) -> Option<Vec<Field<'a>>> {
+        let captures = self
+            .patterns
+            .iter()
+            .find_map(|pattern| pattern.captures(prefix))?;
+        let mut fields = Vec::new();
+        let mut add = |tag, value| {
+            fields.push(Field { tag, value });
+        };
+        if let Some(time) = captures.name("time").map(|time| time.as_str().trim()) {
+            // Times are written as UTCTimestamps where they have a date, so they are formatted
+            // and compared like SendingTime.
+            let value = match find_time(time) {
+                Some((Some(date), time_of_day)) => {
+                    Cow::Owned(format_date_time(date + time_of_day, fraction_digits(time)))
+                }
+                _ => Cow::Borrowed(time),
+            };
+            add(LOG_TIME, value);
+        }
+        if let Some(direction) = captures.name("direction") {
+            add(DIRECTION, direction_name(direction.as_str().trim()));
+        }
+        if let Some(session) = captures.name("session") {
+            add(SESSION, Cow::Borrowed(session.as_str().trim()));
+        }
+        fields.retain(|field| !field.value.is_empty());
+        Some(fields)
+    }
+}
+
+// The precision of a logged time, the fraction may follow a . or a , as in 10:00:00,250.
+fn fraction_digits(time: &str) -> usize {
+    let Some((seconds, fraction)) = time.rsplit_once(['.', ',']) else {
+        return 0;
+    };
+    if !seconds.contains(':') {
+        return 0;
+    }
+    fraction
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .count()
+        .min(9)
+}
+
+// Engines write directions in many ways, they are all given as Sent or Received.
+fn direction_name(direction: &str) -> Cow<'_, str> {
+    match direction.to_ascii_lowercase().as_str() {
+        "in" | "incoming" | "inbound" | "received" | "recv" | "<" | "<-" | "<<" => {
+            Cow::Borrowed("Received")
+        }
+        "out" | "outgoing" | "outbound" | "sent" | "send" | ">" | "->" | ">>" => {
+            Cow::Borrowed("Sent")
+        }
+        _ => Cow::Borrowed(direction),
+    }
+}
+
+pub(crate) fn field_name(tag: usize) -> Option<&'static str> {
+    NAMES.get(tag.checked_sub(LOG_TIME)?).copied()
+}
+
+pub(crate) fn field_tag(name: &str) -> Option<usize> {
+    NAMES
+        .iter()
+        .position(|field| field.eq_ignore_ascii_case(name))
+        .map(|i| LOG_TIME + i)
+}
+
+pub(crate) fn is_log_field(tag: usize) -> bool {
+    field_name(tag).is_some()
+}
+
+impl Printer {
+    // Parses the FIX message on a line, adding the fields of the first log format to match the
+    // text before it.
+    pub(crate) fn parse_line<'a>(&self, line: &'a str) -> FixMsg<'a> {
+        let mut parsed = parse_fix_msg(line);
+        let formats = &self.options().log_formats;
+        if formats.is_empty() {
+            return parsed;
+        }
+        if let FixMsg::Full(fix_msg) | FixMsg::Partial(fix_msg) = &mut parsed {
+            let prefix = &line[..message_start(line)];
+            if let Some(fields) = formats.iter().find_map(|format| format.fields(prefix)) {
+                fix_msg.splice(0..0, fields);
+            }
+        }
+        parsed
+    }
+}
+
+// Where the FIX message starts, at its BeginString or else its first field.
+fn message_start(line: &str) -> usize {
+    if let Some(start) = line.find("8=FIX") {
+        return start;
+    }
+    let bytes = line.as_bytes();
+    let equals = (1..bytes.len()).find(|&i| bytes[i] == b'=' && bytes[i - 1].is_ascii_digit());
+    let Some(mut start) = equals else {
+        return line.len();
+    };
+    while start > 0 && bytes[start - 1].is_ascii_digit() {
+        start -= 1;
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_tag, tag_number, tests::run_lines, Expr, Options, Template};
+
+    #[test]
+    fn log_format_case() {
+        let log_formats = vec![
+            LogFormat::parse("quickfixj").unwrap(),
+            LogFormat::parse(r"^(?P<time>\S+ \S+) (?P<direction>IN|OUT) ").unwrap(),
+        ];
+        let options = Options {
+            summary: Some(Template::parse("{LogTime} {Direction} {Session} {MsgType}").unwrap()),
+            filters: vec![Expr::parse("Direction != \"Received\"", &[]).unwrap()],
+            log_formats,
+            ..Options::default()
+        };
+        let input = "<20240101-10:00:00.000, FIX.4.4:OMS->VENUE, outgoing> (8=FIX.4.4|35=D|10=000|)\n\
+                     <20240101-10:00:00.100, FIX.4.4:OMS->VENUE, incoming> (8=FIX.4.4|35=8|10=000|)\n\
+                     2024-01-01 10:00:00,25 OUT 8=FIX.4.4|35=F|10=000|\n\
+                     8=FIX.4.4|35=0|10=000|\n";
+        assert_eq!(
+            run_lines(options, input.lines()),
+            "20240101-10:00:00.000 Sent FIX.4.4:OMS->VENUE D\n\
+             20240101-10:00:00.25 Sent  F\n"
+        );
+        assert_eq!(
+            tag_number("direction"),
+            Some(tag_number("LogTime").unwrap() + 1)
+        );
+        assert!(LogFormat::parse("(?P<other>x)").is_err());
+        // Numbers past the tags of real fields are not read as tags, or taken for log fields.
+        assert_eq!(
+            parse_fix_msg("35=D|536870912=X|99999999999999999999=Y"),
+            FixMsg::Partial(vec![Field {
+                tag: 35,
+                value: Cow::Borrowed("D")
+            }])
+        );
+        assert_eq!(parse_tag("536870912"), None);
+    }
+}
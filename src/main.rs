@@ -1,43 +1,370 @@
 mod command;
-mod prefix;
+mod config;
 
-use std::io;
+use clap::{parser::ValueSource, ArgMatches};
+use config::Config;
+use prefix::{
+    Aggregate, Computed, Diagram, Expr, FixMsg, Highlight, LogFormat, Options, Output, Printer,
+    Rule, Template, Theme, TimeBound, TimeFormat, TimeSource,
+};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+};
 
 fn main() {
-    let matches = command::make_command().get_matches();
-    let flags = prefix::matches_to_flags(&matches);
+    let matches = get_matches();
+    let printer = Printer::new(matches_to_flags(&matches));
 
-    // Avoid compiling regexes multiple times.
-    let msg_regex = prefix::get_msg_regex();
-    let tag_regex = prefix::get_tag_regex();
-    let summary_regexes = prefix::get_summary_regexes(&flags);
+    let stdout = io::stdout();
+    let interactive = stdout.is_terminal();
+    let mut out = Output::new(BufWriter::new(stdout.lock()), interactive);
 
-    if let Some(msgs) = matches.get_many::<String>("message") {
-        let n_msgs = msgs.len();
-        for (i, msg) in msgs.enumerate() {
-            let is_last_line = i + 1 == n_msgs;
-            prefix::run(
-                msg,
-                is_last_line,
-                &msg_regex,
-                &tag_regex,
-                &summary_regexes,
-                &flags,
-            );
+    if let Some(("diff", diff)) = matches.subcommand() {
+        let parse = |id| {
+            let msg = diff.get_one::<String>(id).unwrap();
+            match prefix::parse_fix_msg(msg) {
+                FixMsg::Full(fields) | FixMsg::Partial(fields) => fields,
+                FixMsg::None => {
+                    eprintln!("prefix: diff: no FIX fields found in '{msg}'");
+                    process::exit(2);
+                }
+            }
+        };
+        let result = printer.write_diff(&mut out, &parse("left"), &parse("right"));
+        handle_error(out.flush(), "stdout");
+        match result {
+            Ok(changed) => process::exit(changed as i32),
+            Err(error) => handle_error(Err(error), "stdout"),
+        }
+    } else if let Some(("reconcile", reconcile)) = matches.subcommand() {
+        let read = |id| {
+            let path = reconcile.get_one::<String>(id).unwrap();
+            match File::open(path).and_then(|file| prefix::read_fills(BufReader::new(file))) {
+                Ok(fills) => fills,
+                Err(error) => {
+                    eprintln!("prefix: {path}: {error}");
+                    process::exit(2);
+                }
+            }
+        };
+        let result = prefix::reconcile(read("ours"), read("theirs"));
+        let ours = reconcile.get_one::<String>("ours").unwrap();
+        let theirs = reconcile.get_one::<String>("theirs").unwrap();
+        handle_error(
+            printer.write_reconciliation(&mut out, &result, ours, theirs),
+            "stdout",
+        );
+        handle_error(out.flush(), "stdout");
+        process::exit(!result.is_clean() as i32);
+    } else if let Some(("merge", merge)) = matches.subcommand() {
+        let logs = merge
+            .get_many::<String>("logs")
+            .unwrap()
+            .map(|log| {
+                let (label, path) = match log.split_once('=') {
+                    Some((label, path)) => (label.to_string(), path),
+                    None => {
+                        let name = Path::new(log).file_name().unwrap_or(log.as_ref());
+                        (name.to_string_lossy().into_owned(), log.as_str())
+                    }
+                };
+                match File::open(path) {
+                    Ok(file) => (label, BufReader::new(file)),
+                    Err(error) => {
+                        eprintln!("prefix: {path}: {error}");
+                        process::exit(2);
+                    }
+                }
+            })
+            .collect();
+        handle_error(printer.run_merged(&mut out, logs), "merge");
+    } else if let Some(msgs) = matches.get_many::<String>("message") {
+        for msg in msgs {
+            handle_error(printer.run(&mut out, msg), "stdout");
+        }
+    } else if let Some(files) = matches.get_many::<String>("file") {
+        for file in files {
+            handle_error(printer.run_file(&mut out, file), file);
         }
     } else {
-        let mut lines = io::stdin().lines().peekable();
-        while let Some(line) = lines.next() {
-            let line = line.unwrap();
-            let is_last_line = lines.peek().is_none();
-            prefix::run(
-                &line,
-                is_last_line,
-                &msg_regex,
-                &tag_regex,
-                &summary_regexes,
-                &flags,
-            );
+        let stdin = BufReader::new(io::stdin());
+        handle_error(printer.run_reader(&mut out, stdin), "stdin");
+    }
+    if !printer.options().stats.is_empty() {
+        handle_error(printer.write_stats(&mut out), "stdout");
+    }
+    if printer.options().sessions {
+        handle_error(printer.write_sessions(&mut out), "stdout");
+    }
+    if printer.options().diagram.is_some() {
+        handle_error(printer.write_diagram(&mut out), "stdout");
+    }
+    if !printer.options().split_by.is_empty() {
+        handle_error(printer.flush_split(), "--out-dir");
+    }
+    handle_error(out.flush(), "stdout");
+}
+
+fn get_matches() -> ArgMatches {
+    let args: Vec<OsString> = env::args_os().collect();
+    let matches = command::make_command().get_matches_from(&args);
+    if matches.get_flag("no-config") {
+        return matches;
+    }
+    let layers = match Config::load() {
+        Ok(Some(config)) => config.defaults().and_then(|defaults| {
+            let profile = match matches.get_one::<String>("profile") {
+                Some(profile) => config.profile(profile)?,
+                None => Vec::new(),
+            };
+            Ok((defaults, profile))
+        }),
+        Ok(None) => match matches.get_one::<String>("profile") {
+            Some(profile) => Err(format!("profile '{profile}' used without a config file")),
+            None => Ok((Vec::new(), Vec::new())),
+        },
+        Err(error) => Err(error),
+    };
+    let (defaults, profile) = layers.unwrap_or_else(|error| {
+        eprintln!("prefix: {error}");
+        process::exit(1);
+    });
+    if defaults.is_empty() && profile.is_empty() {
+        return matches;
+    }
+    // The command line overrides a profile, which overrides the defaults.
+    let command = command::make_command();
+    let mut given: Vec<&str> = command
+        .get_arguments()
+        .map(|arg| arg.get_id().as_str())
+        .filter(|&id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        .collect();
+    let profile = config::without_overridden(&command, profile, &given);
+    given.extend(profile.iter().map(|(flag, _)| flag.as_str()));
+    let defaults = config::without_overridden(&command, defaults, &given);
+    let args = args[..1]
+        .iter()
+        .cloned()
+        .chain(
+            defaults
+                .into_iter()
+                .chain(profile)
+                .map(|(_, arg)| OsString::from(arg)),
+        )
+        .chain(args[1..].iter().cloned());
+    command::make_command().get_matches_from(args)
+}
+
+fn matches_to_flags(matches: &ArgMatches) -> Options {
+    let when = matches.get_one::<String>("color").unwrap();
+    // https://no-color.org, only an explicit --color=always will override it.
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    // Split messages are written to files rather than the terminal.
+    let to_terminal = io::stdout().is_terminal() && matches.get_one::<String>("split-by").is_none();
+    let use_colour = (to_terminal && !no_color && when == "auto") || when == "always";
+    let mut theme = match env::var("PREFIX_COLORS") {
+        Ok(spec) => Theme::parse(&spec).unwrap_or_else(|error| {
+            eprintln!("prefix: PREFIX_COLORS: {error}");
+            process::exit(1);
+        }),
+        Err(_) => Theme::default(),
+    };
+    for (id, header) in [("rule", false), ("header-rule", true)] {
+        for spec in matches.get_many::<String>(id).unwrap_or_default() {
+            let rule = Rule::parse(spec).unwrap_or_else(|error| {
+                eprintln!("prefix: --{id}: {error}");
+                process::exit(1);
+            });
+            theme.rules.push(Rule { header, ..rule });
+        }
+    }
+    if matches.get_flag("semantic") {
+        theme.rules.extend(Theme::semantic_rules());
+    }
+    let delimiter;
+    let strip;
+    if matches.get_flag("porcelain") {
+        delimiter = String::from("\x01");
+        strip = true;
+    } else {
+        delimiter = matches.get_one::<String>("delimiter").unwrap().to_string();
+        strip = matches.get_flag("strip");
+    }
+    let highlights = matches
+        .get_many::<String>("highlight")
+        .unwrap_or_default()
+        .map(|spec| {
+            Highlight::parse(spec).unwrap_or_else(|error| {
+                eprintln!("prefix: --highlight: {error}");
+                process::exit(1);
+            })
+        })
+        .collect();
+    let mut computed = Vec::new();
+    for definition in matches.get_many::<String>("define").unwrap_or_default() {
+        match Computed::parse(definition, &computed) {
+            Ok(field) => computed.push(field),
+            Err(error) => {
+                eprintln!("prefix: --define: {error}");
+                process::exit(1);
+            }
+        }
+    }
+    let filters = matches
+        .get_many::<String>("filter")
+        .unwrap_or_default()
+        .map(|filter| {
+            Expr::parse(filter, &computed).unwrap_or_else(|error| {
+                eprintln!("prefix: --filter: {error}");
+                process::exit(1);
+            })
+        })
+        .collect();
+    let time = match matches.get_one::<String>("time") {
+        Some(time) => TimeFormat::parse(time).unwrap_or_else(|error| {
+            eprintln!("prefix: --time: {error}");
+            process::exit(1);
+        }),
+        None => TimeFormat::Utc,
+    };
+    let bound = |id: &str| {
+        matches.get_one::<String>(id).map(|bound| {
+            TimeBound::parse(bound).unwrap_or_else(|error| {
+                eprintln!("prefix: --{id}: {error}");
+                process::exit(1);
+            })
+        })
+    };
+    let (since, until) = (bound("since"), bound("until"));
+    let time_source = match matches.get_one::<String>("time-source") {
+        Some(source) if source.eq_ignore_ascii_case("line") => TimeSource::Line,
+        Some(tag) => TimeSource::Tag(prefix::parse_tag(tag).unwrap_or_else(|| {
+            eprintln!("prefix: --time-source: unknown tag '{tag}'");
+            process::exit(1);
+        })),
+        None => TimeSource::default(),
+    };
+    let diagram = match matches.get_one::<String>("output").map(String::as_str) {
+        None | Some("text") => None,
+        Some(diagram) => Some(Diagram::parse(diagram).unwrap_or_else(|error| {
+            eprintln!("prefix: --output: {error}");
+            process::exit(1);
+        })),
+    };
+    let log_formats = matches
+        .get_many::<String>("log-format")
+        .unwrap_or_default()
+        .map(|format| {
+            LogFormat::parse(format).unwrap_or_else(|error| {
+                eprintln!("prefix: --log-format: {error}");
+                process::exit(1);
+            })
+        })
+        .collect();
+    let split_by = match matches.get_one::<String>("split-by") {
+        Some(tags) => tags
+            .split([',', ' '])
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| {
+                prefix::parse_tag(tag).unwrap_or_else(|| {
+                    eprintln!("prefix: --split-by: unknown tag '{tag}'");
+                    process::exit(1);
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let stats = match matches.get_one::<String>("stats") {
+        Some(tags) => tags
+            .split([',', ' '])
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| {
+                prefix::parse_field(tag, &computed).unwrap_or_else(|| {
+                    eprintln!("prefix: --stats: unknown tag '{tag}'");
+                    process::exit(1);
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let aggregates = matches
+        .get_many::<String>("aggregate")
+        .unwrap_or_default()
+        .map(|spec| {
+            Aggregate::parse_with(spec, &computed).unwrap_or_else(|error| {
+                eprintln!("prefix: --aggregate: {error}");
+                process::exit(1);
+            })
+        })
+        .collect();
+    let mut summary = None;
+    let mut summaries = HashMap::new();
+    for template in matches.get_many::<String>("summary").unwrap_or_default() {
+        match Template::parse_for_msg_type(template, &computed) {
+            Ok((Some(msg_type), template)) => {
+                summaries.insert(msg_type, template);
+            }
+            Ok((None, template)) => summary = Some(template),
+            Err(error) => {
+                eprintln!("prefix: --summary: {error}");
+                process::exit(1);
+            }
+        }
+    }
+    Options {
+        delimiter,
+        // Diagrams are read by other tools, so they are never coloured.
+        colour: use_colour && diagram.is_none(),
+        theme,
+        only_fix: matches.get_flag("only-fix"),
+        repeating: matches.get_flag("repeating"),
+        strict: matches.get_flag("strict"),
+        strip,
+        summary,
+        summaries,
+        tag: matches.get_flag("tag"),
+        value: matches.get_flag("value"),
+        highlights,
+        changes: matches.get_flag("changes"),
+        stats,
+        aggregates,
+        csv: matches.get_flag("csv"),
+        sessions: matches.get_flag("sessions"),
+        resolve_rejects: matches.get_flag("resolve-rejects"),
+        direction: matches.get_flag("direction") || matches.contains_id("me"),
+        me: matches.get_one::<String>("me").cloned(),
+        diagram,
+        computed,
+        filters,
+        time,
+        since,
+        until,
+        time_source,
+        log_formats,
+        out_dir: matches
+            .get_one::<String>("out-dir")
+            .map_or_else(|| PathBuf::from("."), PathBuf::from),
+        raw: matches.get_flag("raw"),
+        split_by,
+    }
+}
+
+fn handle_error(result: io::Result<()>, input: &str) {
+    if let Err(error) = result {
+        // When piping into certain programs like head, printing to stdout can fail. This is
+        // expected and we do not want to panic, instead we terminate cleanly. Prefix is not
+        // designed to be used for anything besides printing. And this keeps the behaviour closer
+        // to other unix tools that will terminate upon receiving the SIGPIPE (which rust programs ignore by default)
+        if error.kind() == io::ErrorKind::BrokenPipe {
+            process::exit(0);
         }
+        eprintln!("prefix: {input}: {error}");
+        process::exit(1);
     }
 }
@@ -0,0 +1,120 @@
+use crate::{file::read_line, write_colour, FixMsg, Output, Printer};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io::{self, BufRead, Write},
+};
+
+// The next line of a log waiting to be printed, and the time it is ordered by.
+struct Source<R> {
+    label: String,
+    reader: R,
+    buffer: Vec<u8>,
+    line: String,
+    time: i64,
+}
+
+impl Printer {
+    /// Prints the lines of several logs as one, in the order of the times of their messages, with
+    /// each line labelled by the log it came from. Lines without a time, such as those that are
+    /// not FIX messages, stay after the line before them. Only a line of each log is held at once.
+    pub fn run_merged<R: BufRead>(
+        &self,
+        out: &mut Output<impl Write>,
+        logs: Vec<(String, R)>,
+    ) -> io::Result<()> {
+        let width = logs
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or_default();
+        let mut sources: Vec<Source<R>> = logs
+            .into_iter()
+            .map(|(label, reader)| Source {
+                label,
+                reader,
+                buffer: Vec::new(),
+                line: String::new(),
+                time: i64::MIN,
+            })
+            .collect();
+        // Ties keep the order the logs were given in.
+        let mut heap = BinaryHeap::new();
+        for (i, source) in sources.iter_mut().enumerate() {
+            if self.read_next(source)? {
+                heap.push(Reverse((source.time, i)));
+            }
+        }
+        let flags = self.options();
+        while let Some(Reverse((_, i))) = heap.pop() {
+            let source = &mut sources[i];
+            let mut chunk = Output::new(Vec::new(), false);
+            self.run(&mut chunk, &source.line)?;
+            let mut label = Vec::new();
+            let padded = format!("{:width$}", source.label);
+            write_colour(&mut label, padded, &flags.theme.header, flags.colour)?;
+            label.extend(b"  ");
+            chunk.prefix_lines(&label);
+            out.append(chunk)?;
+            if out.line_buffered() {
+                out.flush()?;
+            }
+            if self.read_next(source)? {
+                heap.push(Reverse((source.time, i)));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_next<R: BufRead>(&self, source: &mut Source<R>) -> io::Result<bool> {
+        let Some(line) = read_line(&mut source.reader, &mut source.buffer)? else {
+            return Ok(false);
+        };
+        source.line.clear();
+        source.line.push_str(&line);
+        let time = match self.parse_line(&source.line) {
+            FixMsg::Full(fix_msg) | FixMsg::Partial(fix_msg) => {
+                self.message_time(&fix_msg, &source.line)
+            }
+            FixMsg::None => None,
+        };
+        if let Some(time) = time {
+            source.time = time;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, Output, Template};
+
+    #[test]
+    fn merge_case() {
+        let oms = b"8=FIX.4.4|35=D|52=20240101-10:00:00.000|11=1\n\
+                   8=FIX.4.4|35=F|52=20240101-10:00:00.300|11=2\n\
+                   not a message \xff\n";
+        let venue = b"8=FIX.4.4|35=8|52=20240101-10:00:00.100|11=1\r\n\
+                     8=FIX.4.4|35=8|52=20240101-10:00:00.300|11=2\n";
+        let options = Options {
+            summary: Some(Template::parse("35 11").unwrap()),
+            ..Options::default()
+        };
+        let printer = Printer::new(options);
+        let mut out = Output::new(Vec::new(), false);
+        let logs = vec![
+            (String::from("oms"), &oms[..]),
+            (String::from("venue"), &venue[..]),
+        ];
+        printer.run_merged(&mut out, logs).unwrap();
+        assert_eq!(
+            String::from_utf8(out.into_inner()).unwrap(),
+            "oms    D 1\n\
+             venue  8 1\n\
+             oms    F 2\n\
+             oms    not a message \u{FFFD}\n\
+             venue  8 2\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{self, Write};
+
+pub struct Output<W: Write> {
+    writer: W,
+    // Flush after every line so an interactive user sees each message as soon as it is parsed.
+    line_buffered: bool,
+    // The empty line printed after each message is held back until something else is written, so
+    // the output never ends with an empty line and nothing has to wait to know it was the last.
+    pending_newline: bool,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(writer: W, line_buffered: bool) -> Self {
+        Output {
+            writer,
+            line_buffered,
+            pending_newline: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn line_buffered(&self) -> bool {
+        self.line_buffered
+    }
+
+    pub fn defer_newline(&mut self) {
+        self.pending_newline = true;
+    }
+
+    // Append the output of a chunk that was written separately, keeping any newline it held back.
+    pub fn append(&mut self, chunk: Output<Vec<u8>>) -> io::Result<()> {
+        let pending_newline = chunk.pending_newline;
+        let chunk = chunk.into_inner();
+        if !chunk.is_empty() {
+            self.write_all(&chunk)?;
+            self.pending_newline = pending_newline;
+        }
+        Ok(())
+    }
+}
+
+impl Output<Vec<u8>> {
+    // Start every line written so far with a prefix, such as the label of the log it came from.
+    pub(crate) fn prefix_lines(&mut self, prefix: &[u8]) {
+        if self.writer.is_empty() {
+            return;
+        }
+        let mut prefixed = Vec::with_capacity(self.writer.len() + prefix.len());
+        for line in self.writer.split_inclusive(|&b| b == b'\n') {
+            prefixed.extend_from_slice(prefix);
+            prefixed.extend_from_slice(line);
+        }
+        self.writer = prefixed;
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.pending_newline && !buf.is_empty() {
+            self.writer.write_all(b"\n")?;
+            self.pending_newline = false;
+        }
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, Printer};
+
+    #[test]
+    fn output_case() {
+        let printer = Printer::new(Options {
+            strip: true,
+            ..Options::default()
+        });
+        let mut chunk = Output::new(Vec::new(), false);
+        printer.run(&mut chunk, "8=FIX|35=D").unwrap();
+        let mut out = Output::new(Vec::new(), false);
+        printer.run(&mut out, "log line").unwrap();
+        out.append(chunk).unwrap();
+        printer.run(&mut out, "35=8").unwrap();
+        out.append(Output::new(Vec::new(), false)).unwrap();
+        assert_eq!(
+            String::from_utf8(out.into_inner()).unwrap(),
+            "log line\nBeginString=FIX\nMsgType=D\n\nMsgType=8\n"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use memchr::{memchr, memchr3};
+use std::borrow::Cow;
+
+// Tags from here up are left for the fields prefix adds to messages, such as those read by a log
+// format, so larger numbers are not read as tags.
+pub(crate) const TAG_LIMIT: usize = 1 << 29;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Field<'a> {
+    pub tag: usize,
+    pub value: Cow<'a, str>,
+}
+
+impl Field<'_> {
+    pub fn into_owned(self) -> Field<'static> {
+        Field {
+            tag: self.tag,
+            value: Cow::Owned(self.value.into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FixMsg<'a> {
+    Full(Vec<Field<'a>>),
+    Partial(Vec<Field<'a>>),
+    None,
+}
+
+pub fn parse_fix_msg(input: &str) -> FixMsg<'_> {
+    let mut contains_begin_string = false;
+    let mut contains_check_sum = false;
+
+    let mut result = Vec::new();
+    for field in fields(input) {
+        if field.tag == 8 {
+            contains_begin_string = true;
+        } else if field.tag == 10 {
+            contains_check_sum = true;
+        }
+        result.push(field);
+    }
+    if result.is_empty() {
+        // If a log file is being piped in, it's expected to have some lines without FIX messages.
+        return FixMsg::None;
+    }
+    if !contains_begin_string || !contains_check_sum {
+        return FixMsg::Partial(result);
+    }
+    FixMsg::Full(result)
+}
+
+// Iterates over every valid field in the input, a number followed by an = followed by anything
+// excluding the given delimiters. Current delimiters used: ^ | SOH
+pub fn fields(input: &str) -> Fields<'_> {
+    Fields { input, pos: 0 }
+}
+
+pub struct Fields<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Field<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only valid fields are returned and any malformed fields will be ignored.
+        // This means its very unlikely for prefix to fail to parse a FIX message.
+        let bytes = self.input.as_bytes();
+        while let Some(offset) = memchr(b'=', &bytes[self.pos..]) {
+            let equals = self.pos + offset;
+            let mut tag_start = equals;
+            while tag_start > self.pos && bytes[tag_start - 1].is_ascii_digit() {
+                tag_start -= 1;
+            }
+            let value_start = equals + 1;
+            let value_end = memchr3(b'^', b'|', b'\x01', &bytes[value_start..])
+                .map_or(bytes.len(), |i| value_start + i);
+            if tag_start == equals || value_start == value_end {
+                self.pos = value_start;
+                continue;
+            }
+            // All of the bytes searched for are ASCII so every index lies on a char boundary.
+            let tag = &self.input[tag_start..equals];
+            self.pos = value_end;
+            let Some(tag) = tag.parse().ok().filter(|&tag| tag < TAG_LIMIT) else {
+                continue;
+            };
+            return Some(Field {
+                tag,
+                value: Cow::Borrowed(&self.input[value_start..value_end]),
+            });
+        }
+        self.pos = bytes.len();
+        None
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{
+    diff,
+    file::read_line,
+    parse_fix_msg,
+    tags::{self, value, LAST_PX, LAST_QTY, MSG_TYPE, ORDER_ID},
+    translate_value, write_colour, Field, FieldDiff, FixMsg, Printer,
+};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+const EXEC_ID: usize = 17;
+const EXEC_TYPE: usize = 150;
+
+// The fields that make a fill's economics, which matched fills must agree on.
+const COMPARED: [usize; 6] = [ORDER_ID, 15, 54, 55, LAST_QTY, LAST_PX];
+
+// Printed for each fill when there is no summary template.
+const DESCRIPTION: [usize; 6] = [EXEC_ID, ORDER_ID, 55, 54, LAST_QTY, LAST_PX];
+
+type Fill = Vec<Field<'static>>;
+
+/// The result of matching the fills in our log against the fills in a counterparty or drop copy
+/// log.
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    pub matched: usize,
+    /// Fills only found in their log.
+    pub missing: Vec<Fill>,
+    /// Fills only found in our log.
+    pub extra: Vec<Fill>,
+    /// Matched fills that disagree on the value of a field, ours first.
+    pub mismatched: Vec<(Fill, Fill)>,
+}
+
+impl Reconciliation {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Whether a message is an execution report for a trade, ExecType Trade, PartialFill or Fill.
+pub fn is_fill(fix_msg: &[Field]) -> bool {
+    let value = |tag| fix_msg.iter().find(|field| field.tag == tag);
+    value(MSG_TYPE).is_some_and(|field| field.value == "8")
+        && value(EXEC_TYPE).is_some_and(|field| matches!(field.value.as_ref(), "F" | "1" | "2"))
+}
+
+/// Reads every fill from a log, lines that are not fills are skipped.
+pub fn read_fills(mut reader: impl BufRead) -> io::Result<Vec<Fill>> {
+    let mut fills = Vec::new();
+    let mut buffer = Vec::new();
+    while let Some(line) = read_line(&mut reader, &mut buffer)? {
+        if let FixMsg::Full(fix_msg) | FixMsg::Partial(fix_msg) = parse_fix_msg(&line) {
+            if is_fill(&fix_msg) {
+                fills.push(fix_msg.into_iter().map(Field::into_owned).collect());
+            }
+        }
+    }
+    Ok(fills)
+}
+
+/// Pairs our fills with theirs by ExecID, then any left over by OrderID, LastQty and LastPx as a
+/// drop copy may assign its own ExecIDs.
+pub fn reconcile(ours: Vec<Fill>, theirs: Vec<Fill>) -> Reconciliation {
+    let mut theirs: Vec<Option<Fill>> = theirs.into_iter().map(Some).collect();
+    let mut pairs = Vec::new();
+    let mut unmatched = Vec::new();
+
+    let by_exec_id = index(&theirs, |fill| Some(value(fill, EXEC_ID)?.to_string()));
+    for fill in ours {
+        match take(
+            &mut theirs,
+            &by_exec_id,
+            value(&fill, EXEC_ID).map(str::to_string),
+        ) {
+            Some(their_fill) => pairs.push((fill, their_fill)),
+            None => unmatched.push(fill),
+        }
+    }
+    let by_trade = index(&theirs, trade_key);
+    let mut extra = Vec::new();
+    for fill in unmatched {
+        match take(&mut theirs, &by_trade, trade_key(&fill)) {
+            Some(their_fill) => pairs.push((fill, their_fill)),
+            None => extra.push(fill),
+        }
+    }
+
+    let mut result = Reconciliation {
+        missing: theirs.into_iter().flatten().collect(),
+        extra,
+        ..Reconciliation::default()
+    };
+    for (ours, theirs) in pairs {
+        if mismatches(&ours, &theirs).is_empty() {
+            result.matched += 1;
+        } else {
+            result.mismatched.push((ours, theirs));
+        }
+    }
+    result
+}
+
+// Quantities and prices are compared as numbers, so 100 and 100.00 are the same fill. Any other
+// value, such as an ID, is compared as it is.
+fn normalise(tag: usize, value: &str) -> String {
+    match value.parse::<f64>() {
+        Ok(number) if [LAST_QTY, LAST_PX].contains(&tag) => number.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn trade_key(fill: &[Field]) -> Option<String> {
+    let key = [ORDER_ID, LAST_QTY, LAST_PX].map(|tag| value(fill, tag).map(|v| normalise(tag, v)));
+    Some(format!(
+        "{}|{}|{}",
+        key[0].as_ref()?,
+        key[1].as_ref()?,
+        key[2].as_ref()?
+    ))
+}
+
+// Positions of the fills with each key, in the order they were found.
+fn index(
+    fills: &[Option<Fill>],
+    key: impl Fn(&[Field]) -> Option<String>,
+) -> HashMap<String, Vec<usize>> {
+    let mut index = HashMap::<String, Vec<usize>>::new();
+    for (i, fill) in fills.iter().enumerate() {
+        if let Some(key) = fill.as_deref().and_then(&key) {
+            index.entry(key).or_default().push(i);
+        }
+    }
+    index
+}
+
+fn take(
+    fills: &mut [Option<Fill>],
+    index: &HashMap<String, Vec<usize>>,
+    key: Option<String>,
+) -> Option<Fill> {
+    index.get(&key?)?.iter().find_map(|&i| fills[i].take())
+}
+
+// The compared fields both sides have but disagree on, fields only one side sends are expected as
+// counterparties rarely send the same set of optional fields.
+fn mismatches<'a, 'b>(ours: &'b [Field<'a>], theirs: &'b [Field<'a>]) -> Vec<FieldDiff<'a, 'b>> {
+    diff(ours, theirs)
+        .into_iter()
+        .filter(|diff| COMPARED.contains(&diff.tag))
+        .filter(|diff| match (diff.left, diff.right) {
+            (Some(left), Some(right)) => {
+                normalise(diff.tag, &left.value) != normalise(diff.tag, &right.value)
+            }
+            _ => false,
+        })
+        .collect()
+}
+
+impl Printer {
+    /// Prints the missing, extra and mismatched fills of a reconciliation, with the fields that
+    /// differ under each mismatched fill.
+    pub fn write_reconciliation(
+        &self,
+        out: &mut impl Write,
+        result: &Reconciliation,
+        ours: &str,
+        theirs: &str,
+    ) -> io::Result<()> {
+        if !result.missing.is_empty() {
+            let heading = format!("missing, only in {theirs}:");
+            self.write_heading(out, result.missing.len(), &heading)?;
+            for fill in &result.missing {
+                writeln!(out, "  {}", self.describe_fill(fill))?;
+            }
+        }
+        if !result.extra.is_empty() {
+            let heading = format!("extra, only in {ours}:");
+            self.write_heading(out, result.extra.len(), &heading)?;
+            for fill in &result.extra {
+                writeln!(out, "  {}", self.describe_fill(fill))?;
+            }
+        }
+        if !result.mismatched.is_empty() {
+            let heading = format!("mismatched, {ours} -> {theirs}:");
+            self.write_heading(out, result.mismatched.len(), &heading)?;
+            for (left, right) in &result.mismatched {
+                writeln!(out, "  {}", self.describe_fill(left))?;
+                let diffs = mismatches(left, right);
+                self.write_field_diffs(out, &diffs, left, right, "    ")?;
+            }
+        }
+        self.write_heading(out, result.matched, "matched")
+    }
+
+    fn write_heading(&self, out: &mut impl Write, count: usize, text: &str) -> io::Result<()> {
+        let flags = self.options();
+        let fills = if count == 1 { "fill" } else { "fills" };
+        let heading = format!("{count} {fills} {text}");
+        write_colour(out, heading, &flags.theme.header, flags.colour)?;
+        writeln!(out)
+    }
+
+    fn describe_fill(&self, fill: &[Field]) -> String {
+        if let Some(summary) = self.summarise(fill) {
+            return summary;
+        }
+        DESCRIPTION
+            .iter()
+            .filter_map(|&tag| fill.iter().find(|field| field.tag == tag))
+            .map(|field| format!("{}={}", tags::TAGS[field.tag], translate_value(field)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+
+    #[test]
+    fn reconcile_case() {
+        let ours = "35=8|17=A|37=X|150=F|32=100|31=1.1\n\
+            35=8|17=B|37=X|150=F|32=50|31=1.2\n\
+            35=8|17=C|37=Y|150=0\n\
+            35=8|17=D|37=Z|150=F|32=10|31=2";
+        let theirs = "35=8|17=a|37=X|150=F|32=100.0|31=1.10\n\
+            35=8|17=B|37=X|150=F|32=50|31=1.3|1=ACCOUNT|60=20240101-10:00:00\n\
+            35=8|17=E|37=Y|150=2|32=5|31=3\n\
+            35=8|17=F|37=0Z|150=F|32=10|31=2";
+        let result = reconcile(
+            read_fills(ours.as_bytes()).unwrap(),
+            read_fills(theirs.as_bytes()).unwrap(),
+        );
+        let printer = Printer::new(Options::default());
+        let mut out = Vec::new();
+        printer
+            .write_reconciliation(&mut out, &result, "ours", "theirs")
+            .unwrap();
+        let expected = "2 fills missing, only in theirs:\n  \
+            ExecID=E OrderID=Y LastQty=5 LastPx=3\n  \
+            ExecID=F OrderID=0Z LastQty=10 LastPx=2\n\
+            1 fill extra, only in ours:\n  \
+            ExecID=D OrderID=Z LastQty=10 LastPx=2\n\
+            1 fill mismatched, ours -> theirs:\n  \
+            ExecID=B OrderID=X LastQty=50 LastPx=1.2\n    \
+            ~ LastPx = 1.2 -> 1.3\n\
+            1 fill matched\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert!(!result.is_clean());
+
+        let fills = read_fills(&b"35=8|17=A|150=F|58=\xff\r\n"[..]).unwrap();
+        assert_eq!(fills[0][3].value, "\u{FFFD}");
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    format_to_summary, tag_name,
+    tags::{value, MSG_TYPE, SENDER_COMP_ID, TARGET_COMP_ID},
+    translate_value, write_fix_msg, Field, Highlight, Printer,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Write},
+    sync::Arc,
+};
+
+const MSG_SEQ_NUM: usize = 34;
+const REF_SEQ_NUM: usize = 45;
+const REF_TAG_ID: usize = 371;
+
+// How many of the latest messages from one CompID to another are kept for rejects to refer to.
+const WINDOW: usize = 10_000;
+
+type Message = Arc<Vec<Field<'static>>>;
+
+// The latest messages sent from one CompID to another by their MsgSeqNum, so a reject can find the
+// message it refers to, along with the order they were added in to forget the oldest.
+#[derive(Default)]
+pub struct Messages {
+    sessions: HashMap<(String, String), Session>,
+}
+
+#[derive(Default)]
+struct Session {
+    by_seq_num: HashMap<String, Message>,
+    order: VecDeque<String>,
+}
+
+impl Messages {
+    pub(crate) fn insert(&mut self, fix_msg: &[Field]) {
+        let [sender, target, seq_num] = [SENDER_COMP_ID, TARGET_COMP_ID, MSG_SEQ_NUM]
+            .map(|tag| value(fix_msg, tag).unwrap_or_default().to_string());
+        let session = self.sessions.entry((sender, target)).or_default();
+        let owned = fix_msg.iter().map(|f| f.clone().into_owned()).collect();
+        if session
+            .by_seq_num
+            .insert(seq_num.clone(), Arc::new(owned))
+            .is_none()
+        {
+            session.order.push_back(seq_num);
+        }
+        if session.order.len() > WINDOW {
+            if let Some(oldest) = session.order.pop_front() {
+                session.by_seq_num.remove(&oldest);
+            }
+        }
+    }
+
+    // A reject is sent back by the receiver of the message, so the CompIDs are the other way round.
+    fn referenced(&self, reject: &[Field]) -> Option<Message> {
+        let [sender, target, seq_num] = [TARGET_COMP_ID, SENDER_COMP_ID, REF_SEQ_NUM]
+            .map(|tag| value(reject, tag).unwrap_or_default().to_string());
+        self.sessions
+            .get(&(sender, target))?
+            .by_seq_num
+            .get(&seq_num)
+            .cloned()
+    }
+}
+
+impl Printer {
+    // Prints the message a Reject or BusinessMessageReject refers to under it, indented and with
+    // the field named by its RefTagID highlighted, or without colour repeated on a line marked ^.
+    pub(crate) fn write_referenced(
+        &self,
+        out: &mut impl Write,
+        fix_msg: &[Field],
+    ) -> io::Result<()> {
+        let mut messages = self.messages.lock().unwrap();
+        messages.insert(fix_msg);
+        if !matches!(value(fix_msg, MSG_TYPE), Some("3" | "j")) {
+            return Ok(());
+        }
+        let Some(referenced) = messages.referenced(fix_msg) else {
+            return Ok(());
+        };
+        drop(messages);
+
+        let mut flags = self.options().clone();
+        let ref_tag = value(fix_msg, REF_TAG_ID).and_then(|tag| tag.trim().parse().ok());
+        if let Some(tag) = ref_tag {
+            flags.highlights.push(Highlight { tag, value: None });
+        }
+        let mut buffer = Vec::new();
+        if let Some(summary) = format_to_summary(&referenced, &flags) {
+            buffer.extend(summary.bytes());
+        } else {
+            write_fix_msg(&mut buffer, &referenced, &flags)?;
+        }
+        // With colour the delimiter is followed by a reset, which is kept after the last line.
+        let text = String::from_utf8_lossy(&buffer);
+        let (lines, rest) = match text.rfind('\n') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => (text.as_ref(), ""),
+        };
+        for line in lines.split('\n') {
+            writeln!(out, "  {line}")?;
+        }
+        out.write_all(rest.as_bytes())?;
+        let field = ref_tag.and_then(|tag| referenced.iter().find(|field| field.tag == tag));
+        match field {
+            Some(field) if !flags.colour => {
+                let name = match tag_name(field.tag) {
+                    Some(name) if flags.value => name.to_string(),
+                    _ => field.tag.to_string(),
+                };
+                let value = if flags.value {
+                    translate_value(field).to_string()
+                } else {
+                    field.value.to_string()
+                };
+                writeln!(out, "  ^ {name}={value}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_with, Options};
+
+    #[test]
+    fn reject_case() {
+        let printer = Printer::new(Options {
+            delimiter: String::from("|"),
+            strip: true,
+            value: true,
+            resolve_rejects: true,
+            ..Options::default()
+        });
+        let lines = [
+            "35=D|49=BUY|56=SELL|34=5|44=abc",
+            "35=D|49=SELL|56=BUY|34=5|44=1.1",
+            "35=3|49=SELL|56=BUY|34=6|45=5|371=44|372=D",
+            "35=j|49=SELL|56=BUY|34=7|45=9|372=D",
+        ];
+        let expected = "MsgType=NewOrderSingle|SenderCompID=BUY|TargetCompID=SELL|MsgSeqNum=5|Price=abc|\n\
+            MsgType=NewOrderSingle|SenderCompID=SELL|TargetCompID=BUY|MsgSeqNum=5|Price=1.1|\n\
+            MsgType=Reject|SenderCompID=SELL|TargetCompID=BUY|MsgSeqNum=6|RefSeqNum=5|RefTagID=Price|RefMsgType=NewOrderSingle|\n  \
+            MsgType=NewOrderSingle|SenderCompID=BUY|TargetCompID=SELL|MsgSeqNum=5|Price=abc|\n  \
+            ^ Price=abc\n\
+            MsgType=BusinessMessageReject|SenderCompID=SELL|TargetCompID=BUY|MsgSeqNum=7|RefSeqNum=9|RefMsgType=NewOrderSingle|\n";
+        assert_eq!(run_with(&printer, lines), expected);
+
+        // Only the latest messages of each session are kept.
+        let heartbeats = (10..10_011).map(|seq_num| format!("35=0|49=BUY|56=SELL|34={seq_num}"));
+        run_with(&printer, heartbeats);
+        let rejects = [
+            "35=3|49=SELL|56=BUY|34=8|45=10",
+            "35=3|49=SELL|56=BUY|34=9|45=11",
+        ];
+        let expected =
+            "MsgType=Reject|SenderCompID=SELL|TargetCompID=BUY|MsgSeqNum=8|RefSeqNum=10|\n\
+            MsgType=Reject|SenderCompID=SELL|TargetCompID=BUY|MsgSeqNum=9|RefSeqNum=11|\n  \
+            MsgType=Heartbeat|SenderCompID=BUY|TargetCompID=SELL|MsgSeqNum=11|\n";
+        assert_eq!(run_with(&printer, rejects), expected);
+    }
+}
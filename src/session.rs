@@ -0,0 +1,351 @@
+use crate::{
+    tag_name,
+    tags::{value, MSG_TYPE, SENDER_COMP_ID, SENDING_TIME, TARGET_COMP_ID},
+    time::{format_duration, parse_timestamp},
+    translate_value, value_name, write_colour, Field, Printer,
+};
+use std::io::{self, Write};
+
+const HEART_BT_INT: usize = 108;
+const TEST_REQ_ID: usize = 112;
+
+// Details printed for each Logon and Logout, ResetSeqNumFlag, Text and SessionStatus.
+const EVENT_TAGS: [usize; 4] = [HEART_BT_INT, 141, 58, 1409];
+
+// A counterparty waits for the heartbeat interval plus some transmission time before sending a
+// TestRequest, 20% is the usual allowance, so only longer gaps count as a silence.
+const TOLERANCE: f64 = 1.2;
+
+#[derive(Debug)]
+struct Event {
+    time: String,
+    sender: String,
+    msg_type: String,
+    details: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Direction {
+    sender: String,
+    count: usize,
+    last: Option<(i64, String)>,
+    longest_gap: Option<i64>,
+}
+
+#[derive(Debug)]
+struct Silence {
+    sender: String,
+    from: String,
+    to: String,
+    gap: i64,
+}
+
+#[derive(Debug)]
+struct TestRequest {
+    time: Option<(i64, String)>,
+    sender: String,
+    id: String,
+    // How long the Heartbeat took, if there was one.
+    answered: Option<Option<i64>>,
+}
+
+#[derive(Debug)]
+struct Session {
+    // The CompIDs of the first message seen, later messages may have them either way around.
+    sender: String,
+    target: String,
+    heart_bt_int: Option<i64>,
+    // The latest message from either side.
+    last: Option<(i64, String)>,
+    events: Vec<Event>,
+    directions: Vec<Direction>,
+    silences: Vec<Silence>,
+    test_requests: Vec<TestRequest>,
+}
+
+// Every session seen so far in the order they started, identified by their pair of CompIDs
+// regardless of which side sent the message.
+#[derive(Debug, Default)]
+pub(crate) struct Sessions {
+    sessions: Vec<Session>,
+}
+
+impl Sessions {
+    pub(crate) fn add(&mut self, fix_msg: &[Field]) {
+        let (Some(sender), Some(target)) = (
+            value(fix_msg, SENDER_COMP_ID),
+            value(fix_msg, TARGET_COMP_ID),
+        ) else {
+            return;
+        };
+        let position = self.sessions.iter().position(|session| {
+            (session.sender == sender && session.target == target)
+                || (session.sender == target && session.target == sender)
+        });
+        let session = match position {
+            Some(position) => &mut self.sessions[position],
+            None => {
+                self.sessions.push(Session {
+                    sender: sender.to_string(),
+                    target: target.to_string(),
+                    heart_bt_int: None,
+                    last: None,
+                    events: Vec::new(),
+                    directions: Vec::new(),
+                    silences: Vec::new(),
+                    test_requests: Vec::new(),
+                });
+                self.sessions.last_mut().unwrap()
+            }
+        };
+        session.add(sender, fix_msg);
+    }
+}
+
+fn is_silence(heart_bt_int: Option<i64>, gap: i64) -> bool {
+    heart_bt_int.is_some_and(|heart_bt_int| gap as f64 > heart_bt_int as f64 * 1e9 * TOLERANCE)
+}
+
+impl Session {
+    // A side that stopped sending before the end of the log, without logging out, is silent until
+    // the last message of the session.
+    fn final_silences(&self) -> impl Iterator<Item = Silence> + '_ {
+        self.directions.iter().filter_map(|direction| {
+            let (last, from) = direction.last.as_ref()?;
+            let (end, to) = self.last.as_ref()?;
+            let gap = end - last;
+            is_silence(self.heart_bt_int, gap).then(|| Silence {
+                sender: direction.sender.clone(),
+                from: from.clone(),
+                to: to.clone(),
+                gap,
+            })
+        })
+    }
+
+    fn add(&mut self, sender: &str, fix_msg: &[Field]) {
+        let msg_type = value(fix_msg, MSG_TYPE).unwrap_or_default();
+        let sending_time = value(fix_msg, SENDING_TIME);
+        let time = sending_time.and_then(|sending_time| {
+            Some((parse_timestamp(sending_time)?, sending_time.to_string()))
+        });
+
+        let position = self.directions.iter().position(|d| d.sender == sender);
+        let direction = match position {
+            Some(position) => &mut self.directions[position],
+            None => {
+                self.directions.push(Direction {
+                    sender: sender.to_string(),
+                    count: 0,
+                    last: None,
+                    longest_gap: None,
+                });
+                self.directions.last_mut().unwrap()
+            }
+        };
+        direction.count += 1;
+        if let (Some((now, to)), Some((last, from))) = (&time, &direction.last) {
+            let gap = now - last;
+            direction.longest_gap = Some(direction.longest_gap.map_or(gap, |g| g.max(gap)));
+            if is_silence(self.heart_bt_int, gap) {
+                self.silences.push(Silence {
+                    sender: sender.to_string(),
+                    from: from.clone(),
+                    to: to.clone(),
+                    gap,
+                });
+            }
+        }
+        if time.is_some() {
+            direction.last = time.clone();
+            self.last = time.clone();
+        }
+
+        match msg_type {
+            "A" | "5" => {
+                if msg_type == "A" {
+                    if let Some(interval) = value(fix_msg, HEART_BT_INT) {
+                        self.heart_bt_int = interval.trim().parse().ok();
+                    }
+                } else {
+                    // The silence after a Logout is expected, so start again from the next Logon.
+                    direction.last = None;
+                }
+                let details = fix_msg
+                    .iter()
+                    .filter(|field| EVENT_TAGS.contains(&field.tag))
+                    .map(|field| {
+                        let name = tag_name(field.tag)
+                            .map_or_else(|| field.tag.to_string(), str::to_string);
+                        format!("{name}={}", translate_value(field))
+                    })
+                    .collect();
+                self.events.push(Event {
+                    time: sending_time.unwrap_or_default().to_string(),
+                    sender: sender.to_string(),
+                    msg_type: msg_type.to_string(),
+                    details,
+                });
+            }
+            "1" => self.test_requests.push(TestRequest {
+                time,
+                sender: sender.to_string(),
+                id: value(fix_msg, TEST_REQ_ID).unwrap_or_default().to_string(),
+                answered: None,
+            }),
+            "0" => {
+                let Some(id) = value(fix_msg, TEST_REQ_ID) else {
+                    return;
+                };
+                let request = self.test_requests.iter_mut().find(|request| {
+                    request.answered.is_none() && request.sender != sender && request.id == id
+                });
+                if let Some(request) = request {
+                    let delay = match (&request.time, &time) {
+                        (Some((sent, _)), Some((now, _))) => Some(now - sent),
+                        _ => None,
+                    };
+                    request.answered = Some(delay);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn msg_type_name(msg_type: &str) -> &str {
+    value_name(MSG_TYPE, msg_type).unwrap_or(msg_type)
+}
+
+impl Printer {
+    /// Prints the Logon and Logout messages of every session, how the gaps between the messages
+    /// sent by each side compare to the HeartBtInt, any silences longer than it and whether each
+    /// TestRequest was answered by a Heartbeat.
+    pub fn write_sessions(&self, out: &mut impl Write) -> io::Result<()> {
+        let flags = self.options();
+        let theme = &flags.theme;
+        let sessions = self.sessions.lock().unwrap();
+        for (i, session) in sessions.sessions.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            let name = format!("{} <-> {}", session.sender, session.target);
+            write_colour(out, name, &theme.header, flags.colour)?;
+            writeln!(out)?;
+            let width = session
+                .directions
+                .iter()
+                .map(|direction| direction.sender.chars().count())
+                .max()
+                .unwrap_or_default();
+            for event in &session.events {
+                let line = format!(
+                    "  {:6}  {}  {:width$}  {}",
+                    msg_type_name(&event.msg_type),
+                    event.time,
+                    event.sender,
+                    event.details.join(" ")
+                );
+                writeln!(out, "{}", line.trim_end())?;
+            }
+            match session.heart_bt_int {
+                Some(heart_bt_int) => writeln!(out, "  HeartBtInt {heart_bt_int}s")?,
+                None => writeln!(out, "  HeartBtInt unknown, no Logon was seen")?,
+            }
+            for direction in &session.directions {
+                let messages = if direction.count == 1 {
+                    "message"
+                } else {
+                    "messages"
+                };
+                let gap = match direction.longest_gap {
+                    Some(gap) => format!(", longest gap {}", format_duration(gap)),
+                    None => String::new(),
+                };
+                writeln!(
+                    out,
+                    "  {:width$}  {} {messages}{gap}",
+                    direction.sender, direction.count
+                )?;
+            }
+            let final_silences: Vec<Silence> = session.final_silences().collect();
+            for silence in session.silences.iter().chain(&final_silences) {
+                let line = format!(
+                    "  Silent  {} to {}  {:width$}  {}",
+                    silence.from,
+                    silence.to,
+                    silence.sender,
+                    format_duration(silence.gap)
+                );
+                write_colour(out, line, &theme.highlight, flags.colour)?;
+                writeln!(out)?;
+            }
+            for request in &session.test_requests {
+                let time = request.time.as_ref().map_or("", |(_, time)| time.as_str());
+                let line = format!(
+                    "  {}  {time}  {:width$}  TestReqID={}",
+                    msg_type_name("1"),
+                    request.sender,
+                    request.id
+                );
+                match request.answered {
+                    Some(Some(delay)) => {
+                        writeln!(out, "{line}  answered in {}", format_duration(delay))?
+                    }
+                    Some(None) => writeln!(out, "{line}  answered")?,
+                    None => {
+                        write_colour(
+                            out,
+                            format!("{line}  unanswered"),
+                            &theme.highlight,
+                            flags.colour,
+                        )?;
+                        writeln!(out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_with, Options};
+
+    #[test]
+    fn session_case() {
+        let printer = Printer::new(Options {
+            value: true,
+            sessions: true,
+            ..Options::default()
+        });
+        let lines = [
+            "35=A|49=BUY|56=SELL|52=20240102-10:00:00.000|108=30",
+            "35=A|49=SELL|56=BUY|52=20240102-10:00:00.050|108=30|1409=0",
+            "35=0|49=SELL|56=BUY|52=20240102-10:00:30.050",
+            "35=1|49=BUY|56=SELL|52=20240102-10:01:06.000|112=T1",
+            "35=0|49=SELL|56=BUY|52=20240102-10:01:06.250|112=T1",
+            "35=1|49=BUY|56=SELL|52=20240102-10:01:20.000|112=T2",
+            "35=5|49=BUY|56=SELL|52=20240102-10:02:00.000|58=Timeout",
+        ];
+        assert_eq!(run_with(&printer, lines), "");
+        let mut result = Vec::new();
+        printer.write_sessions(&mut result).unwrap();
+        let expected = "BUY <-> SELL\n  \
+            Logon   20240102-10:00:00.000  BUY   HeartBtInt=30\n  \
+            Logon   20240102-10:00:00.050  SELL  HeartBtInt=30 1409=0\n  \
+            Logout  20240102-10:02:00.000  BUY   Text=Timeout\n  \
+            HeartBtInt 30s\n  \
+            BUY   4 messages, longest gap 66.000s\n  \
+            SELL  3 messages, longest gap 36.200s\n  \
+            Silent  20240102-10:00:00.000 to 20240102-10:01:06.000  BUY   66.000s\n  \
+            Silent  20240102-10:00:30.050 to 20240102-10:01:06.250  SELL  36.200s\n  \
+            Silent  20240102-10:01:20.000 to 20240102-10:02:00.000  BUY   40.000s\n  \
+            Silent  20240102-10:01:06.250 to 20240102-10:02:00.000  SELL  53.750s\n  \
+            TestRequest  20240102-10:01:06.000  BUY   TestReqID=T1  answered in 0.250s\n  \
+            TestRequest  20240102-10:01:20.000  BUY   TestReqID=T2  unanswered\n";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+    }
+}
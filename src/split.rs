@@ -0,0 +1,153 @@
+use crate::{Field, Output, Printer};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// How many files are kept open at once, well under the usual limit of 1024 open files.
+const MAX_OPEN: usize = 256;
+
+// The files most recently written to, kept open since a log interleaves many sessions, along with
+// when each was last used. Files closed to stay under the limit are reopened to append to them.
+#[derive(Default)]
+pub(crate) struct Files {
+    open: HashMap<String, (Output<BufWriter<File>>, u64)>,
+    created: HashSet<String>,
+    uses: u64,
+}
+
+impl Files {
+    fn get(&mut self, dir: &Path, name: &str) -> io::Result<&mut Output<BufWriter<File>>> {
+        self.uses += 1;
+        if !self.open.contains_key(name) {
+            if self.open.len() >= MAX_OPEN {
+                let oldest = self
+                    .open
+                    .iter()
+                    .min_by_key(|(_, (_, used))| *used)
+                    .map(|(name, _)| name.clone())
+                    .unwrap();
+                let (mut out, _) = self.open.remove(&oldest).unwrap();
+                out.flush()?;
+            }
+            let file = if self.created.contains(name) {
+                OpenOptions::new().append(true).open(dir.join(name))?
+            } else {
+                fs::create_dir_all(dir)?;
+                let file = File::create(dir.join(name))?;
+                self.created.insert(name.to_string());
+                file
+            };
+            let out = Output::new(BufWriter::new(file), false);
+            self.open.insert(name.to_string(), (out, 0));
+        }
+        let (out, used) = self.open.get_mut(name).unwrap();
+        *used = self.uses;
+        Ok(out)
+    }
+}
+
+// Values are made safe to use in a file name, so EUR/USD -> EUR-USD.
+fn file_name(values: &[&str]) -> String {
+    let name: Vec<String> = values
+        .iter()
+        .map(|value| match value.trim() {
+            "" => String::from("none"),
+            value => value
+                .chars()
+                .map(|c| match c {
+                    c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') => c,
+                    _ => '-',
+                })
+                .collect(),
+        })
+        .collect();
+    let name = name.join("_");
+    match name.strip_prefix('.') {
+        Some(rest) => format!("-{rest}.log"),
+        None => format!("{name}.log"),
+    }
+}
+
+impl Printer {
+    // Writes a message to the file named after its values of the split tags, as it would be
+    // printed or as the line it was read from.
+    pub(crate) fn write_split(&self, fix_msg: &[Field], line: &str) -> io::Result<()> {
+        let flags = self.options();
+        if !self.matches_filters(fix_msg, line) {
+            return Ok(());
+        }
+        let values: Vec<&str> = flags
+            .split_by
+            .iter()
+            .map(|&tag| {
+                fix_msg
+                    .iter()
+                    .find(|field| field.tag == tag)
+                    .map_or("", |field| field.value.as_ref())
+            })
+            .collect();
+        let name = file_name(&values);
+        let mut files = self.files.lock().unwrap();
+        let out = files.get(&flags.out_dir, &name)?;
+        if flags.raw {
+            writeln!(out, "{line}")
+        } else {
+            self.print_fix_msg(out, fix_msg, line)
+        }
+    }
+
+    /// Flushes every file written by [`Options::split_by`](crate::Options), returning the first
+    /// error any of them had.
+    pub fn flush_split(&self) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        for (out, _) in files.open.values_mut() {
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_with, Options};
+
+    #[test]
+    fn split_case() {
+        let out_dir = std::env::temp_dir().join(format!("prefix-split-{}", std::process::id()));
+        let options = Options {
+            split_by: vec![49, 55],
+            out_dir: out_dir.clone(),
+            raw: true,
+            ..Options::default()
+        };
+        let printer = Printer::new(options);
+        let input = "8=FIX.4.4|35=D|49=OMS|55=EUR/USD|11=1\n\
+                     8=FIX.4.4|35=D|49=OMS|55=GBP/USD|11=2\n\
+                     8=FIX.4.4|35=D|49=OMS|55=EUR/USD|11=3\n\
+                     8=FIX.4.4|35=0|49=OMS|\n";
+        assert_eq!(run_with(&printer, input.lines()), "");
+        printer.flush_split().unwrap();
+        let read = |name| std::fs::read_to_string(out_dir.join(name)).unwrap();
+        assert_eq!(
+            read("OMS_EUR-USD.log"),
+            "8=FIX.4.4|35=D|49=OMS|55=EUR/USD|11=1\n8=FIX.4.4|35=D|49=OMS|55=EUR/USD|11=3\n"
+        );
+        assert_eq!(
+            read("OMS_GBP-USD.log"),
+            "8=FIX.4.4|35=D|49=OMS|55=GBP/USD|11=2\n"
+        );
+        assert_eq!(read("OMS_none.log"), "8=FIX.4.4|35=0|49=OMS|\n");
+
+        // Files closed to keep under the limit of open files are appended to when reopened.
+        run_with(&printer, (0..300).map(|i| format!("35=0|49=OMS|55={i}")));
+        run_with(&printer, ["35=1|49=OMS|55=0"]);
+        printer.flush_split().unwrap();
+        assert_eq!(read("OMS_0.log"), "35=0|49=OMS|55=0\n35=1|49=OMS|55=0\n");
+        assert_eq!(read("OMS_299.log"), "35=0|49=OMS|55=299\n");
+        std::fs::remove_dir_all(out_dir).unwrap();
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{
+    expr::{field_name, format_number, parse_field, with_computed},
+    tags::LAST_QTY,
+    translate_value, write_colour, Computed, Field, FixMsg, Printer,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// A numeric aggregate of a tag, computed for each group of [`Options::stats`](crate::Options).
+/// Every instance of the tag in a message is included, so repeating groups are aggregated too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Sum(usize),
+    Avg(usize),
+    Min(usize),
+    Max(usize),
+    /// The average price weighted by quantity, LastQty unless another tag is given.
+    Vwap {
+        price: usize,
+        quantity: usize,
+    },
+}
+
+impl Aggregate {
+    /// Parses `FUNCTION:TAG` where the function is sum, avg, min, max or vwap, vwap also takes
+    /// the tag to weight by, as in `vwap:Price:OrderQty`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        Aggregate::parse_with(input, &[])
+    }
+
+    /// Parses an aggregate that may be of one of the computed fields given.
+    pub fn parse_with(input: &str, computed: &[Computed]) -> Result<Self, String> {
+        let parts: Vec<&str> = input.split(':').collect();
+        let tag = |i: usize| {
+            let tag = parts
+                .get(i)
+                .ok_or_else(|| format!("'{input}' must be FUNCTION:TAG"))?;
+            parse_field(tag, computed).ok_or_else(|| format!("unknown tag '{tag}'"))
+        };
+        let (aggregate, len) = match parts[0].to_ascii_lowercase().as_str() {
+            "sum" => (Aggregate::Sum(tag(1)?), 2),
+            "avg" => (Aggregate::Avg(tag(1)?), 2),
+            "min" => (Aggregate::Min(tag(1)?), 2),
+            "max" => (Aggregate::Max(tag(1)?), 2),
+            "vwap" if parts.len() > 2 => {
+                let (price, quantity) = (tag(1)?, tag(2)?);
+                (Aggregate::Vwap { price, quantity }, 3)
+            }
+            "vwap" => {
+                let price = tag(1)?;
+                let quantity = LAST_QTY;
+                (Aggregate::Vwap { price, quantity }, 2)
+            }
+            function => {
+                return Err(format!(
+                    "unknown function '{function}', expected sum, avg, min, max or vwap"
+                ))
+            }
+        };
+        if parts.len() > len {
+            return Err(format!("too many parts in '{input}'"));
+        }
+        Ok(aggregate)
+    }
+
+    fn name(&self, computed: &[Computed]) -> String {
+        let name = |tag| field_name(tag, computed);
+        match *self {
+            Aggregate::Sum(tag) => format!("sum({})", name(tag)),
+            Aggregate::Avg(tag) => format!("avg({})", name(tag)),
+            Aggregate::Min(tag) => format!("min({})", name(tag)),
+            Aggregate::Max(tag) => format!("max({})", name(tag)),
+            Aggregate::Vwap { price, quantity } if quantity == LAST_QTY => {
+                format!("vwap({})", name(price))
+            }
+            Aggregate::Vwap { price, quantity } => {
+                format!("vwap({}, {})", name(price), name(quantity))
+            }
+        }
+    }
+}
+
+// The running totals for one aggregate, kept so that totals from different threads can be merged.
+#[derive(Debug, Clone, Copy)]
+struct Total {
+    count: usize,
+    sum: f64,
+    weight: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Total {
+    fn default() -> Self {
+        Total {
+            count: 0,
+            sum: 0.0,
+            weight: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Total {
+    fn add(&mut self, value: f64, weight: f64) {
+        self.count += 1;
+        self.sum += value * weight;
+        self.weight += weight;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn merge(&mut self, other: &Total) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.weight += other.weight;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    fn result(&self, aggregate: &Aggregate) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        match aggregate {
+            Aggregate::Sum(_) => Some(self.sum),
+            Aggregate::Avg(_) => Some(self.sum / self.count as f64),
+            Aggregate::Min(_) => Some(self.min),
+            Aggregate::Max(_) => Some(self.max),
+            Aggregate::Vwap { .. } if self.weight == 0.0 => None,
+            Aggregate::Vwap { .. } => Some(self.sum / self.weight),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Group {
+    count: usize,
+    totals: Vec<Total>,
+}
+
+// The messages counted for each group so far, keyed by the values of the tags grouped by.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    groups: HashMap<Vec<String>, Group>,
+}
+
+impl Stats {
+    pub(crate) fn merge(&mut self, other: Stats) {
+        for (key, other) in other.groups {
+            let group = self.groups.entry(key).or_default();
+            group.count += other.count;
+            group.totals.resize(other.totals.len(), Total::default());
+            for (total, other) in group.totals.iter_mut().zip(&other.totals) {
+                total.merge(other);
+            }
+        }
+    }
+}
+
+fn number(field: &Field) -> Option<f64> {
+    field.value.trim().parse().ok()
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Printer {
+    pub(crate) fn count_line(&self, stats: &mut Stats, input: &str) {
+        match self.parse_line(input) {
+            FixMsg::Full(fix_msg) => self.count_fix_msg(stats, &fix_msg, input),
+            FixMsg::Partial(fix_msg) if !self.options().strict => {
+                self.count_fix_msg(stats, &fix_msg, input)
+            }
+            _ => {}
+        }
+    }
+
+    // Adds a message to the group for its values of the stats tags. A tag repeated in the message
+    // has all of its values joined with commas, the same as combining repeating groups.
+    fn count_fix_msg(&self, stats: &mut Stats, fix_msg: &[Field], line: &str) {
+        let flags = self.options();
+        if !self.matches_filters(fix_msg, line) {
+            return;
+        }
+        let fix_msg = &with_computed(fix_msg, &flags.computed);
+        let key = flags
+            .stats
+            .iter()
+            .map(|&tag| {
+                fix_msg
+                    .iter()
+                    .filter(|field| field.tag == tag)
+                    .map(|field| {
+                        if flags.value {
+                            translate_value(field)
+                        } else {
+                            Cow::Borrowed(field.value.as_ref())
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        let group = stats.groups.entry(key).or_default();
+        group.count += 1;
+        group
+            .totals
+            .resize(flags.aggregates.len(), Total::default());
+        for (aggregate, total) in flags.aggregates.iter().zip(&mut group.totals) {
+            match *aggregate {
+                Aggregate::Sum(tag)
+                | Aggregate::Avg(tag)
+                | Aggregate::Min(tag)
+                | Aggregate::Max(tag) => {
+                    for field in fix_msg.iter().filter(|field| field.tag == tag) {
+                        if let Some(value) = number(field) {
+                            total.add(value, 1.0);
+                        }
+                    }
+                }
+                // Each price is paired with the quantity of the same instance of a repeating group.
+                Aggregate::Vwap { price, quantity } => {
+                    let prices = fix_msg.iter().filter(|field| field.tag == price);
+                    let quantities = fix_msg.iter().filter(|field| field.tag == quantity);
+                    for (price, quantity) in prices.zip(quantities) {
+                        if let (Some(price), Some(quantity)) = (number(price), number(quantity)) {
+                            total.add(price, quantity);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints the statistics gathered from every message run so far, as a table or CSV, with the
+    /// largest groups first.
+    pub fn write_stats(&self, out: &mut impl Write) -> io::Result<()> {
+        let flags = self.options();
+        let stats = self.stats.lock().unwrap();
+        let mut groups: Vec<_> = stats.groups.iter().collect();
+        groups.sort_by(|(a_key, a), (b_key, b)| b.count.cmp(&a.count).then(a_key.cmp(b_key)));
+
+        let mut header: Vec<String> = flags
+            .stats
+            .iter()
+            .map(|&tag| field_name(tag, &flags.computed))
+            .collect();
+        header.push(String::from("Count"));
+        header.extend(
+            flags
+                .aggregates
+                .iter()
+                .map(|aggregate| aggregate.name(&flags.computed)),
+        );
+        let rows: Vec<Vec<String>> = groups
+            .iter()
+            .map(|(key, group)| {
+                let mut row = key.to_vec();
+                row.push(group.count.to_string());
+                row.extend(flags.aggregates.iter().enumerate().map(|(i, aggregate)| {
+                    group
+                        .totals
+                        .get(i)
+                        .and_then(|total| total.result(aggregate))
+                        .map(format_number)
+                        .unwrap_or_default()
+                }));
+                row
+            })
+            .collect();
+
+        if flags.csv {
+            for row in std::iter::once(&header).chain(&rows) {
+                let row: Vec<String> = row.iter().map(|value| csv_escape(value)).collect();
+                writeln!(out, "{}", row.join(","))?;
+            }
+            return Ok(());
+        }
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        for (i, row) in std::iter::once(&header).chain(&rows).enumerate() {
+            let mut line = String::new();
+            for (column, (value, width)) in row.iter().zip(&widths).enumerate() {
+                if column > 0 {
+                    line.push_str("  ");
+                }
+                // The tags grouped by are text, everything after them is a number.
+                if column < flags.stats.len() {
+                    line.push_str(&format!("{value:width$}"));
+                } else {
+                    line.push_str(&format!("{value:>width$}"));
+                }
+            }
+            let line = line.trim_end();
+            if i == 0 {
+                write_colour(out, line, &flags.theme.header, flags.colour)?;
+                writeln!(out)?;
+            } else {
+                writeln!(out, "{line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_with, Options};
+
+    #[test]
+    fn stats_case() {
+        let printer = Printer::new(Options {
+            value: true,
+            stats: vec![35, 55],
+            aggregates: vec![
+                Aggregate::parse("sum:LastQty").unwrap(),
+                Aggregate::parse("vwap:31").unwrap(),
+                Aggregate::parse("max:270").unwrap(),
+            ],
+            ..Options::default()
+        });
+        let lines = [
+            "35=8|55=EUR USD|32=100|31=1.1",
+            "35=8|55=EUR USD|32=300|31=1.2",
+            "35=W|55=EUR USD|269=0|270=1.1|269=1|270=1.3",
+            "not a FIX message",
+            "35=8|55=GBP,USD|32=10|31=1.3",
+        ];
+        assert_eq!(run_with(&printer, lines), "");
+        let mut result = Vec::new();
+        printer.write_stats(&mut result).unwrap();
+        let expected = "MsgType                        Symbol   Count  sum(LastQty)  vwap(LastPx)  max(MDEntryPx)\n\
+            ExecutionReport                EUR USD      2           400         1.175\n\
+            ExecutionReport                GBP,USD      1            10           1.3\n\
+            MarketDataSnapshotFullRefresh  EUR USD      1                                         1.3\n";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+
+        let printer = Printer::new(Options {
+            csv: true,
+            ..printer.options().clone()
+        });
+        run_with(&printer, ["35=8|55=GBP,USD|32=10|31=1.3"]);
+        let mut result = Vec::new();
+        printer.write_stats(&mut result).unwrap();
+        let expected = "MsgType,Symbol,Count,sum(LastQty),vwap(LastPx),max(MDEntryPx)\n\
+            ExecutionReport,\"GBP,USD\",1,10,1.3,\n";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Field;
 use phf::phf_map;
 
 pub const TAGS: [&str; 957] = [
@@ -960,6 +961,41 @@ pub const TAGS: [&str; 957] = [
     "LegInterestAccrualDate",
 ];
 
+// Fields of the FIX 4.4 standard header and trailer.
+pub const HEADER: [usize; 30] = [
+    8, 9, 35, 49, 56, 115, 128, 90, 91, 34, 50, 142, 57, 143, 116, 144, 129, 145, 43, 97, 52, 122,
+    212, 213, 347, 369, 627, 628, 629, 630,
+];
+pub const TRAILER: [usize; 3] = [93, 89, 10];
+
+// The CompIDs of the sender and receiver of a message, which identify its session.
+pub const SENDER_COMP_ID: usize = 49;
+pub const TARGET_COMP_ID: usize = 56;
+
+// Fields looked for by more than one feature.
+pub const LAST_PX: usize = 31;
+pub const LAST_QTY: usize = 32;
+pub const MSG_TYPE: usize = 35;
+pub const ORDER_ID: usize = 37;
+pub const SENDING_TIME: usize = 52;
+
+// The value of the first field of a message with a tag.
+pub fn value<'a>(fix_msg: &'a [Field], tag: usize) -> Option<&'a str> {
+    fix_msg
+        .iter()
+        .find(|field| field.tag == tag)
+        .map(|field| field.value.as_ref())
+}
+
+// MultipleValueString fields of FIX 4.4, their values are space separated lists.
+pub const MULTIPLE_VALUE: [usize; 7] = [18, 276, 277, 286, 291, 292, 529];
+
+// UTCTimestamp and UTCTimeOnly fields of FIX 4.4.
+pub const UTC_TIMESTAMP: [usize; 17] = [
+    42, 52, 60, 62, 122, 126, 168, 341, 342, 343, 344, 345, 367, 443, 483, 769, 779,
+];
+pub const UTC_TIME_ONLY: [usize; 1] = [273];
+
 pub static VALUES: phf::Map<&'static str, &'static str> = phf_map! {
     "4-B" => "Buy",
     "4-S" => "Sell",
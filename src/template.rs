@@ -0,0 +1,422 @@
+use crate::{
+    expr::parse_field,
+    parse_tag_number,
+    tags::{self, MSG_TYPE},
+    value_name, Computed,
+};
+use regex::Regex;
+use std::borrow::Cow;
+
+/// A summary template such as `{MsgType} {Side:>4} {OrderQty:,} {Symbol|"?"}`.
+///
+/// Each `{}` holds a tag name or number, or the name of a computed field, followed by an optional
+/// `|"default"` used when the message does not have the field, and an optional `:format`. The
+/// format is an alignment (`<`, `>` or `^`, after an optional fill character), a width, `,` to
+/// separate the thousands of a number and `.N` to round a number to N decimal places. `{{` and
+/// `}}` are literal braces.
+///
+/// Templates without any braces are read the older way, where any standalone tag number is
+/// replaced by the value of that field and left as it is when the message does not have it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    tag: usize,
+    default: Option<String>,
+    fill: char,
+    align: Align,
+    width: usize,
+    thousands: bool,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Centre,
+}
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        Template::parse_with(input, &[])
+    }
+
+    /// Parses a template that may use the computed fields given.
+    pub fn parse_with(input: &str, computed: &[Computed]) -> Result<Self, String> {
+        if !input.contains(['{', '}']) {
+            return Ok(Template::parse_numbers(input));
+        }
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    let mut quoted = false;
+                    loop {
+                        match chars.next() {
+                            Some('}') if !quoted => break,
+                            Some('"') => {
+                                quoted = !quoted;
+                                placeholder.push('"');
+                            }
+                            Some('\\') if quoted => {
+                                placeholder.push('\\');
+                                placeholder.extend(chars.next());
+                            }
+                            Some(c) => placeholder.push(c),
+                            None => return Err(format!("unclosed '{{' in '{input}'")),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(Placeholder::parse(&placeholder, computed)?));
+                }
+                '}' => {
+                    return Err(format!(
+                        "unmatched '}}' in '{input}', use '}}}}' for a brace"
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Parses a template that may be for messages of a single MsgType, written as `TYPE=TEMPLATE`
+    /// where the type is its value, such as `D`, or its name, such as `NewOrderSingle`. A numeric
+    /// type, such as `8`, is only read as one when the template has braces, as templates in the
+    /// older form such as `8=FIX 35` have none. Returns the value of the MsgType along with the
+    /// template.
+    pub fn parse_for_msg_type(
+        input: &str,
+        computed: &[Computed],
+    ) -> Result<(Option<String>, Self), String> {
+        if let Some((msg_type, template)) = input.split_once('=') {
+            let msg_type = msg_type.trim();
+            let legacy =
+                msg_type.bytes().all(|b| b.is_ascii_digit()) && !template.contains(['{', '}']);
+            if let Some(msg_type) = msg_type_value(msg_type).filter(|_| !legacy) {
+                return Ok((Some(msg_type), Template::parse_with(template, computed)?));
+            }
+        }
+        Ok((None, Template::parse_with(input, computed)?))
+    }
+
+    // Standalone numbers become fields that default to the number itself, so missing fields and
+    // numbers that are not tags are left as they were written.
+    fn parse_numbers(input: &str) -> Self {
+        let numbers = Regex::new(r"\b[0-9]+\b").unwrap();
+        let mut parts = Vec::new();
+        let mut end = 0;
+        for number in numbers.find_iter(input) {
+            let Some(tag) = parse_tag_number(number.as_str()) else {
+                continue;
+            };
+            if tag.to_string() != number.as_str() {
+                continue;
+            }
+            if number.start() > end {
+                parts.push(Part::Text(input[end..number.start()].to_string()));
+            }
+            parts.push(Part::Field(Placeholder {
+                default: Some(number.as_str().to_string()),
+                ..Placeholder::new(tag)
+            }));
+            end = number.end();
+        }
+        if end < input.len() {
+            parts.push(Part::Text(input[end..].to_string()));
+        }
+        Template { parts }
+    }
+
+    // Fills in the template, the lookup gives the value to print for a tag and whether to mark it
+    // with the highlight style, after it has been padded to its width.
+    pub(crate) fn render<'a>(
+        &self,
+        mut lookup: impl FnMut(usize) -> Option<(Cow<'a, str>, bool)>,
+        highlight: &str,
+    ) -> String {
+        let mut result = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => result.push_str(text),
+                Part::Field(placeholder) => {
+                    let (value, highlighted) = match lookup(placeholder.tag) {
+                        Some((value, highlighted)) => (placeholder.format(&value), highlighted),
+                        None => (placeholder.default.clone().unwrap_or_default(), false),
+                    };
+                    let value = placeholder.pad(value);
+                    if highlighted && !highlight.is_empty() {
+                        result.push_str(&format!("\x1b[{highlight}m{value}\x1b[0m"));
+                    } else {
+                        result.push_str(&value);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Placeholder {
+    fn new(tag: usize) -> Self {
+        Placeholder {
+            tag,
+            default: None,
+            fill: ' ',
+            align: Align::Left,
+            width: 0,
+            thousands: false,
+            precision: None,
+        }
+    }
+
+    fn parse(input: &str, computed: &[Computed]) -> Result<Self, String> {
+        let end = input.find(['|', ':']).unwrap_or(input.len());
+        let name = input[..end].trim();
+        let tag = parse_field(name, computed).ok_or_else(|| format!("unknown tag '{name}'"))?;
+        let mut placeholder = Placeholder::new(tag);
+        let mut rest = &input[end..];
+        while !rest.is_empty() {
+            if let Some(default) = rest.strip_prefix('|') {
+                let (value, remaining) = parse_default(default)?;
+                placeholder.default = Some(value);
+                rest = remaining;
+            } else if let Some(format) = rest.strip_prefix(':') {
+                let end = format.find('|').unwrap_or(format.len());
+                placeholder.parse_format(&format[..end])?;
+                rest = &format[end..];
+            } else {
+                return Err(format!("unexpected '{rest}' in '{{{input}}}'"));
+            }
+        }
+        Ok(placeholder)
+    }
+
+    fn parse_format(&mut self, format: &str) -> Result<(), String> {
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Centre),
+            _ => None,
+        };
+        let mut chars: Vec<char> = format.chars().collect();
+        match chars.as_slice() {
+            [fill, c, ..] if align(*c).is_some() => {
+                self.fill = *fill;
+                self.align = align(*c).unwrap();
+                chars.drain(..2);
+            }
+            [c, ..] if align(*c).is_some() => {
+                self.align = align(*c).unwrap();
+                chars.remove(0);
+            }
+            _ => {}
+        }
+        let rest: String = chars.into_iter().collect();
+        let (rest, precision) = match rest.split_once('.') {
+            Some((rest, precision)) => {
+                let precision = precision
+                    .parse()
+                    .map_err(|_| format!("invalid precision '{precision}' in ':{format}'"))?;
+                (rest, Some(precision))
+            }
+            None => (rest.as_str(), None),
+        };
+        self.precision = precision;
+        let width = match rest.strip_suffix(',') {
+            Some(width) => {
+                self.thousands = true;
+                width
+            }
+            None => rest,
+        };
+        if !width.is_empty() {
+            self.width = width
+                .parse()
+                .map_err(|_| format!("invalid width '{width}' in ':{format}'"))?;
+        }
+        Ok(())
+    }
+
+    // Numeric formatting only applies to values that are numbers, anything else is left alone.
+    fn format(&self, value: &str) -> String {
+        if !self.thousands && self.precision.is_none() {
+            return value.to_string();
+        }
+        let Ok(number) = value.trim().parse::<f64>() else {
+            return value.to_string();
+        };
+        let value = match self.precision {
+            Some(precision) => format!("{number:.precision$}"),
+            None => value.trim().to_string(),
+        };
+        if !self.thousands {
+            return value;
+        }
+        let (sign, digits) = match value.strip_prefix(['-', '+']) {
+            Some(digits) => (&value[..1], digits),
+            None => ("", value.as_str()),
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(i) => digits.split_at(i),
+            None => (digits, ""),
+        };
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        format!("{sign}{grouped}{fraction}")
+    }
+
+    fn pad(&self, value: String) -> String {
+        let len = value.chars().count();
+        if len >= self.width {
+            return value;
+        }
+        let padding = self.width - len;
+        let (left, right) = match self.align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Centre => (padding / 2, padding - padding / 2),
+        };
+        let fill = |n| std::iter::repeat_n(self.fill, n).collect::<String>();
+        format!("{}{value}{}", fill(left), fill(right))
+    }
+}
+
+// A default is either quoted, where \" and \\ are escapes, or runs until the format.
+fn parse_default(input: &str) -> Result<(String, &str), String> {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input.find(':').unwrap_or(input.len());
+        return Ok((input[..end].to_string(), &input[end..]));
+    };
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &quoted[i + 1..])),
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            c => value.push(c),
+        }
+    }
+    Err(format!("unclosed '\"' in '{input}'"))
+}
+
+// User defined message types start with U, so they are accepted without being in the dictionary.
+fn msg_type_value(input: &str) -> Option<String> {
+    if value_name(MSG_TYPE, input).is_some()
+        || (input.len() > 1
+            && input.starts_with('U')
+            && input.bytes().all(|b| b.is_ascii_alphanumeric()))
+    {
+        return Some(input.to_string());
+    }
+    tags::VALUES.entries().find_map(|(key, name)| {
+        let value = key.strip_prefix("35-")?;
+        name.eq_ignore_ascii_case(input).then(|| value.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format_to_summary, parse_fix_msg, tests::run_lines, FixMsg, Options};
+    use std::collections::HashMap;
+
+    #[test]
+    fn template_case() {
+        let FixMsg::Partial(input) = parse_fix_msg("35=D|54=1|38=1234567.5|44=1.1") else {
+            panic!("Should be a partial FIX message");
+        };
+        let flags = |template| Options {
+            value: true,
+            summary: Some(Template::parse(template).unwrap()),
+            ..Options::default()
+        };
+        let summary = |template| format_to_summary(&input, &flags(template)).unwrap();
+        assert_eq!(
+            summary(r#"{MsgType} {Side:>4}|{OrderQty:,} {Symbol|"?"} {55|"\"n/a\""}"#),
+            r#"NewOrderSingle  Buy|1,234,567.5 ? "n/a""#
+        );
+        assert_eq!(
+            summary("{{{Price:*^7.2}}} {38:>12,.0} top 5"),
+            "{*1.10**}    1,234,568 top 5"
+        );
+        assert_eq!(summary("35 top 5 of 55"), "NewOrderSingle top 5 of 55");
+        assert!(Template::parse("{Foo}").is_err());
+        assert!(Template::parse("{35").is_err());
+        assert!(Template::parse("35}").is_err());
+        assert!(Template::parse("{35:x}").is_err());
+    }
+
+    #[test]
+    fn msg_type_summary_case() {
+        let (msg_type, template) = Template::parse_for_msg_type("D={Symbol} {Side}", &[]).unwrap();
+        assert_eq!(msg_type.as_deref(), Some("D"));
+        let (fill_type, fill) =
+            Template::parse_for_msg_type("executionreport={OrdStatus}", &[]).unwrap();
+        assert_eq!(fill_type.as_deref(), Some("8"));
+        assert_eq!(Template::parse_for_msg_type("35=55", &[]).unwrap().0, None);
+        // Templates in the older form have no braces, so they are read as they were.
+        let (legacy_type, legacy) = Template::parse_for_msg_type("8=FIX 35=8", &[]).unwrap();
+        assert_eq!(legacy_type, None);
+        assert_eq!(legacy, Template::parse("8=FIX 35=8").unwrap());
+        assert_eq!(
+            Template::parse_for_msg_type("8={OrdStatus}", &[])
+                .unwrap()
+                .0
+                .as_deref(),
+            Some("8")
+        );
+
+        let summaries = HashMap::from([(String::from("D"), template), (String::from("8"), fill)]);
+        let mut options = Options {
+            delimiter: String::from("|"),
+            strip: true,
+            value: true,
+            summaries,
+            ..Options::default()
+        };
+        let run = |options: &Options| {
+            run_lines(
+                options.clone(),
+                ["35=D|55=EUR/USD|54=1", "35=8|39=2", "35=0|112=T"],
+            )
+        };
+        assert_eq!(
+            run(&options),
+            "EUR/USD Buy\nFilled\nMsgType=Heartbeat|TestReqID=T|\n"
+        );
+        options.summary = Some(Template::parse("{MsgType}").unwrap());
+        assert_eq!(run(&options), "EUR/USD Buy\nFilled\nHeartbeat\n");
+    }
+}
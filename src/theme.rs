@@ -0,0 +1,309 @@
+use crate::{log_format, parse_tag, tags, value_name, Field};
+
+/// The colour used for each part of a printed field, written as SGR parameters such as `1;34`.
+/// An empty style leaves that part uncoloured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub tag: String,
+    /// Tags missing from the dictionary, which are printed as their number.
+    pub unknown_tag: String,
+    pub value: String,
+    /// Values translated with [`Options::value`](crate::Options::value).
+    pub translated_value: String,
+    /// The `=` and delimiter of each field.
+    pub separator: String,
+    /// The tag and value of header and trailer fields, overriding the other styles when set.
+    pub header: String,
+    /// Fields given in [`Options::highlights`](crate::Options::highlights).
+    pub highlight: String,
+    /// Lines of a diff for fields only in the right message.
+    pub added: String,
+    /// Lines of a diff for fields only in the left message.
+    pub removed: String,
+    /// Lines of a diff for fields with different values.
+    pub changed: String,
+    /// Styles for particular values, the first matching rule is used.
+    pub rules: Vec<Rule>,
+}
+
+/// Colours the value of a field when it has a given value, or with [`Rule::header`] the whole
+/// header of any message containing that field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub tag: usize,
+    /// Matches either the value itself or its translation.
+    pub value: String,
+    pub style: String,
+    pub header: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            tag: String::new(),
+            unknown_tag: String::new(),
+            value: String::new(),
+            translated_value: String::new(),
+            separator: String::from("33"),
+            header: String::new(),
+            highlight: String::from("7"),
+            added: String::from("32"),
+            removed: String::from("31"),
+            changed: String::from("33"),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Theme {
+    /// Parses `element=style` entries separated by `:`, in the same format as LS_COLORS, for
+    /// example `tag=1;34:separator=2`. Elements that are not listed keep their default style.
+    pub fn parse(spec: &str) -> Result<Theme, String> {
+        let mut theme = Theme::default();
+        for entry in spec.split(':').filter(|entry| !entry.is_empty()) {
+            let Some((element, style)) = entry.split_once('=') else {
+                return Err(format!("missing '=' in '{entry}'"));
+            };
+            check_style(style)?;
+            let field = match element {
+                "tag" => &mut theme.tag,
+                "unknown" => &mut theme.unknown_tag,
+                "value" => &mut theme.value,
+                "translated" => &mut theme.translated_value,
+                "separator" => &mut theme.separator,
+                "header" => &mut theme.header,
+                "highlight" => &mut theme.highlight,
+                "added" => &mut theme.added,
+                "removed" => &mut theme.removed,
+                "changed" => &mut theme.changed,
+                _ => return Err(format!("unknown element '{element}'")),
+            };
+            *field = style.to_string();
+        }
+        Ok(theme)
+    }
+
+    /// Highlights sides, rejected orders and reject messages.
+    pub fn semantic_rules() -> Vec<Rule> {
+        let rule = |tag, value: &str, style: &str, header| Rule {
+            tag,
+            value: value.to_string(),
+            style: style.to_string(),
+            header,
+        };
+        vec![
+            rule(54, "1", "32", false),
+            rule(54, "2", "31", false),
+            rule(54, "5", "31", false),
+            rule(54, "6", "31", false),
+            rule(39, "8", "31", false),
+            rule(150, "8", "31", false),
+            rule(35, "3", "1;31", true),
+            rule(35, "9", "1;31", true),
+            rule(35, "j", "1;31", true),
+        ]
+    }
+
+    // The style for every header field of a message, if any of its fields match a header rule.
+    pub(crate) fn header_rule_style(&self, fix_msg: &[Field]) -> Option<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.header)
+            .find(|rule| fix_msg.iter().any(|field| rule.matches(field)))
+            .map(|rule| rule.style.as_str())
+    }
+
+    pub(crate) fn tag_style<'a>(&'a self, tag: usize, header_rule: Option<&'a str>) -> &'a str {
+        if let Some(style) = header_rule.filter(|_| tags::HEADER.contains(&tag)) {
+            style
+        } else if !self.header.is_empty() && is_header_or_trailer(tag) {
+            &self.header
+        } else if tag >= tags::TAGS.len() {
+            &self.unknown_tag
+        } else {
+            &self.tag
+        }
+    }
+
+    pub(crate) fn value_style<'a>(
+        &'a self,
+        field: &Field,
+        translated: bool,
+        header_rule: Option<&'a str>,
+    ) -> &'a str {
+        if let Some(style) = header_rule.filter(|_| tags::HEADER.contains(&field.tag)) {
+            style
+        } else if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| !rule.header && rule.matches(field))
+        {
+            &rule.style
+        } else if !self.header.is_empty() && is_header_or_trailer(field.tag) {
+            &self.header
+        } else if translated {
+            &self.translated_value
+        } else {
+            &self.value
+        }
+    }
+}
+
+impl Rule {
+    /// Parses a rule written as `TAG=VALUE:STYLE`, the tag and value can be given as either
+    /// numbers or names, for example `Side=Buy:32` or `54=1:32`.
+    pub fn parse(spec: &str) -> Result<Rule, String> {
+        let (field, style) = spec
+            .rsplit_once(':')
+            .ok_or_else(|| format!("missing ':STYLE' in '{spec}'"))?;
+        let (tag, value) = field
+            .split_once('=')
+            .ok_or_else(|| format!("missing '=VALUE' in '{spec}'"))?;
+        check_style(style)?;
+        let tag = parse_tag(tag).ok_or_else(|| format!("unknown tag '{tag}'"))?;
+        Ok(Rule {
+            tag,
+            value: value.to_string(),
+            style: style.to_string(),
+            header: false,
+        })
+    }
+
+    fn matches(&self, field: &Field) -> bool {
+        field.tag == self.tag && value_matches(field, &self.value)
+    }
+}
+
+/// A field to highlight, any value of the tag matches unless a value is given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub tag: usize,
+    /// Matches either the value itself or its translation.
+    pub value: Option<String>,
+}
+
+impl Highlight {
+    /// Parses `TAG` or `TAG=VALUE`, the tag and value can be given as either numbers or names,
+    /// for example `Symbol` or `11=ABC123`.
+    pub fn parse(spec: &str) -> Result<Highlight, String> {
+        let (tag, value) = match spec.split_once('=') {
+            Some((tag, value)) => (tag, Some(value.to_string())),
+            None => (spec, None),
+        };
+        let tag = parse_tag(tag).ok_or_else(|| format!("unknown tag '{tag}'"))?;
+        Ok(Highlight { tag, value })
+    }
+
+    pub fn matches(&self, field: &Field) -> bool {
+        field.tag == self.tag
+            && self
+                .value
+                .as_ref()
+                .is_none_or(|value| value_matches(field, value))
+    }
+}
+
+fn value_matches(field: &Field, value: &str) -> bool {
+    field.value == value || value_name(field.tag, &field.value) == Some(value)
+}
+
+// What the log says about a message is styled as part of its header.
+fn is_header_or_trailer(tag: usize) -> bool {
+    tags::HEADER.contains(&tag) || tags::TRAILER.contains(&tag) || log_format::is_log_field(tag)
+}
+
+fn check_style(style: &str) -> Result<(), String> {
+    if style.bytes().all(|c| c.is_ascii_digit() || c == b';') {
+        Ok(())
+    } else {
+        Err(format!("invalid style '{style}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fix_msg, write_fix_msg, FixMsg, Options, Printer, Template};
+
+    #[test]
+    fn theme_case() {
+        let FixMsg::Partial(parsed) = parse_fix_msg("35=D|54=1|20001=x") else {
+            panic!("Should be a partial FIX message");
+        };
+        let flags = Options {
+            delimiter: String::from("|"),
+            colour: true,
+            theme: Theme::parse("tag=1:unknown=2:value=3:translated=4:separator=:header=5")
+                .unwrap(),
+            value: true,
+            ..Options::default()
+        };
+        let mut result = Vec::new();
+        write_fix_msg(&mut result, &parsed, &flags).unwrap();
+        let expected = "\x1b[5mMsgType\x1b[0m = \x1b[5mNewOrderSingle\x1b[0m|\
+            \x1b[1mSide\x1b[0m = \x1b[4mBuy\x1b[0m|\
+            \x1b[2m20001\x1b[0m = \x1b[3mx\x1b[0m|";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+        assert!(Theme::parse("tag=red").is_err());
+        assert!(Theme::parse("border=1").is_err());
+    }
+
+    #[test]
+    fn semantic_case() {
+        let FixMsg::Partial(parsed) = parse_fix_msg("35=9|54=1|39=8") else {
+            panic!("Should be a partial FIX message");
+        };
+        let mut theme = Theme {
+            separator: String::new(),
+            ..Theme::default()
+        };
+        theme
+            .rules
+            .push(Rule::parse("OrdStatus=Rejected:7").unwrap());
+        theme.rules.extend(Theme::semantic_rules());
+        let flags = Options {
+            delimiter: String::from("|"),
+            colour: true,
+            theme,
+            strip: true,
+            ..Options::default()
+        };
+        let mut result = Vec::new();
+        write_fix_msg(&mut result, &parsed, &flags).unwrap();
+        let expected = "\x1b[1;31mMsgType\x1b[0m=\x1b[1;31m9\x1b[0m|\
+            Side=\x1b[32m1\x1b[0m|OrdStatus=\x1b[7m8\x1b[0m|";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+        assert!(Rule::parse("54=1").is_err());
+        assert!(Rule::parse("Unknown=1:32").is_err());
+    }
+
+    #[test]
+    fn highlight_case() {
+        let FixMsg::Partial(parsed) = parse_fix_msg("11=A|270=1.1|270=1.2|55=EUR/USD") else {
+            panic!("Should be a partial FIX message");
+        };
+        let flags = Options {
+            delimiter: String::from("|"),
+            colour: true,
+            theme: Theme {
+                separator: String::new(),
+                ..Theme::default()
+            },
+            repeating: true,
+            strip: true,
+            summary: Some(Template::parse("11 55").unwrap()),
+            highlights: vec![
+                Highlight::parse("270=1.2").unwrap(),
+                Highlight::parse("Symbol").unwrap(),
+            ],
+            ..Options::default()
+        };
+        let mut result = Vec::new();
+        write_fix_msg(&mut result, &parsed, &flags).unwrap();
+        let expected = "ClOrdID=A|\x1b[7mMDEntryPx=1.1,1.2\x1b[0m|\x1b[7mSymbol=EUR/USD\x1b[0m|";
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+        let summary = Printer::new(flags).summarise(&parsed).unwrap();
+        assert_eq!(summary, "A \x1b[7mEUR/USD\x1b[0m");
+        assert!(Highlight::parse("Unknown=1").is_err());
+    }
+}
@@ -0,0 +1,467 @@
+use crate::{
+    log_format::LOG_TIME,
+    tags::{self, SENDING_TIME},
+    Field, Printer, TimeZone,
+};
+use std::sync::OnceLock;
+
+const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+const MD_ENTRY_DATE: usize = 272;
+
+/// How UTCTimestamp and UTCTimeOnly fields, such as SendingTime and TransactTime, are printed.
+/// Fields keep the precision they were sent with.
+#[derive(Debug, Clone, Default)]
+pub enum TimeFormat {
+    /// As they are in the message.
+    #[default]
+    Utc,
+    /// In the local time of a zone, followed by its abbreviation.
+    Zone(TimeZone),
+    /// As seconds from a time in nanoseconds since the unix epoch, which is the first time
+    /// printed unless it is already set.
+    Relative(OnceLock<i64>),
+    /// As nanoseconds since the unix epoch.
+    Epoch,
+}
+
+impl TimeFormat {
+    /// Parses `utc`, `relative`, `epoch` or a time zone for [`TimeZone::load`].
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input.to_ascii_lowercase().as_str() {
+            "utc" => Ok(TimeFormat::Utc),
+            "relative" => Ok(TimeFormat::Relative(OnceLock::new())),
+            "epoch" => Ok(TimeFormat::Epoch),
+            _ => TimeZone::load(input).map(TimeFormat::Zone),
+        }
+    }
+
+    // Formats a time field, or returns None for fields that are printed as they are. A
+    // UTCTimeOnly field takes its date from MDEntryDate or else SendingTime.
+    pub(crate) fn format(&self, tag: usize, value: &str, fix_msg: &[Field]) -> Option<String> {
+        if matches!(self, TimeFormat::Utc) {
+            return None;
+        }
+        let time_only = tags::UTC_TIME_ONLY.contains(&tag);
+        if !time_only && !tags::UTC_TIMESTAMP.contains(&tag) && tag != LOG_TIME {
+            return None;
+        }
+        let value = value.trim();
+        let digits = value
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len().min(9));
+        let nanos = if time_only {
+            let date = |tag| {
+                let field = fix_msg.iter().find(|field| field.tag == tag)?;
+                let date = field.value.trim().get(..8)?;
+                parse_timestamp(&format!("{date}-00:00:00"))
+            };
+            let date = date(MD_ENTRY_DATE).or_else(|| date(SENDING_TIME))?;
+            date + parse_timestamp(&format!("19700101-{value}"))?
+        } else {
+            parse_timestamp(value)?
+        };
+        Some(match self {
+            TimeFormat::Utc => return None,
+            TimeFormat::Zone(zone) => {
+                let (offset, abbreviation) = zone.offset(nanos.div_euclid(1_000_000_000));
+                let local = nanos + offset * 1_000_000_000;
+                if time_only {
+                    format!("{} {abbreviation}", format_time_of_day(local, digits))
+                } else {
+                    format!("{} {abbreviation}", format_date_time(local, digits))
+                }
+            }
+            TimeFormat::Relative(origin) => {
+                let offset = nanos - *origin.get_or_init(|| nanos);
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.abs();
+                let fraction = format_fraction(offset, digits);
+                format!("{sign}{}{fraction}", offset / 1_000_000_000)
+            }
+            TimeFormat::Epoch => nanos.to_string(),
+        })
+    }
+}
+
+/// A bound of [`Options::since`](crate::Options) or `until`, either a time in nanoseconds since
+/// the unix epoch or a time of day on the date of the first message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeBound {
+    At(i64),
+    TimeOfDay(i64),
+}
+
+impl TimeBound {
+    /// Parses a date and time such as `2024-01-01T10:00:00`, `2024-01-01 10:00` or
+    /// `20240101-10:00:00.250`, a date alone, or a time of day such as `10:05`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let error = || format!("invalid time '{input}', expected YYYY-MM-DDTHH:MM:SS or HH:MM:SS");
+        let input = input.trim();
+        let date_len = match input.as_bytes().get(4) {
+            Some(b'-') => 10,
+            _ => 8,
+        };
+        let date = input.get(..date_len).map(|date| date.replace('-', ""));
+        if let Some(date) =
+            date.filter(|date| date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()))
+        {
+            let time = input[date_len..].trim_start_matches(['T', 't', ' ', '-']);
+            let time = if time.is_empty() { "00:00" } else { time };
+            let nanos = parse_timestamp(&format!("{date}-{}", full_time(time)));
+            return nanos.map(TimeBound::At).ok_or_else(error);
+        }
+        parse_timestamp(&format!("19700101-{}", full_time(input)))
+            .map(TimeBound::TimeOfDay)
+            .ok_or_else(error)
+    }
+
+    fn resolve(&self, date: i64) -> i64 {
+        match *self {
+            TimeBound::At(time) => time,
+            TimeBound::TimeOfDay(time) => date + time,
+        }
+    }
+}
+
+// Seconds can be left out of a bound, so 10:05 is 10:05:00.
+fn full_time(time: &str) -> String {
+    match time.len() {
+        5 => format!("{time}:00"),
+        _ => time.to_string(),
+    }
+}
+
+/// Where the time of a message compared to `since` and `until` comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    /// A UTCTimestamp or UTCTimeOnly field, SendingTime by default.
+    Tag(usize),
+    /// The first timestamp on the line before the FIX message, as logged by the FIX engine.
+    Line,
+}
+
+impl Default for TimeSource {
+    fn default() -> Self {
+        TimeSource::Tag(SENDING_TIME)
+    }
+}
+
+// Finds the first date and time, or time alone, in some text. A date is YYYYMMDD or YYYY-MM-DD,
+// followed by T, a space or -, and the time is HH:MM:SS with an optional fraction after a . or ,.
+// Returns the date as nanoseconds since the unix epoch and the time of day in nanoseconds.
+pub(crate) fn find_time(text: &str) -> Option<(Option<i64>, i64)> {
+    let bytes = text.as_bytes();
+    (0..bytes.len())
+        .filter(|&i| bytes[i].is_ascii_digit() && (i == 0 || !bytes[i - 1].is_ascii_digit()))
+        .find_map(|i| parse_time_at(&text[i..]))
+}
+
+fn parse_time_at(text: &str) -> Option<(Option<i64>, i64)> {
+    let time_of_day = |text: &str| {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit() && !matches!(c, ':' | '.' | ','))
+            .unwrap_or(text.len());
+        let time = text[..end].replacen(',', ".", 1);
+        let time = time.trim_end_matches(['.', ':']);
+        parse_timestamp(&format!("19700101-{time}"))
+    };
+    let date_len = match text.as_bytes().get(4) {
+        Some(b'-') => 10,
+        _ => 8,
+    };
+    if let Some(date) = text.get(..date_len) {
+        let digits = date.replace('-', "");
+        if let (Some(date), Some(b'T' | b' ' | b'-')) = (
+            parse_timestamp(&format!("{digits}-00:00:00")),
+            text.as_bytes().get(date_len),
+        ) {
+            if let Some(time) = time_of_day(&text[date_len + 1..]) {
+                return Some((Some(date), time));
+            }
+        }
+    }
+    Some((None, time_of_day(text)?))
+}
+
+impl Printer {
+    // The time of a message from the time source, in nanoseconds since the unix epoch. Times
+    // without a date take the date of the first message that had one.
+    pub(crate) fn message_time(&self, fix_msg: &[Field], line: &str) -> Option<i64> {
+        let (date, time_of_day) = match self.options().time_source {
+            TimeSource::Tag(tag) => fix_msg
+                .iter()
+                .find(|field| field.tag == tag)
+                .and_then(|field| find_time(&field.value)),
+            // A time read by a log format is the time on the line.
+            TimeSource::Line => match fix_msg.iter().find(|field| field.tag == LOG_TIME) {
+                Some(field) => find_time(&field.value),
+                None => {
+                    let end = line.find("8=FIX").unwrap_or(line.len());
+                    find_time(&line[..end])
+                }
+            },
+        }?;
+        if let Some(date) = date {
+            self.log_date.get_or_init(|| date);
+        }
+        let log_date = self.log_date.get().copied().unwrap_or_default();
+        Some(date.unwrap_or(log_date) + time_of_day)
+    }
+
+    // Whether the time of a message is within since and until, messages without a time are not.
+    pub(crate) fn in_time_range(&self, fix_msg: &[Field], line: &str) -> bool {
+        let flags = self.options();
+        if flags.since.is_none() && flags.until.is_none() {
+            return true;
+        }
+        let Some(time) = self.message_time(fix_msg, line) else {
+            return false;
+        };
+        let log_date = self.log_date.get().copied().unwrap_or_default();
+        let since = flags.since.map(|since| since.resolve(log_date));
+        let mut until = flags.until.map(|until| until.resolve(log_date));
+        // A range of times of day such as 23:50 to 00:10 runs over midnight.
+        if let (Some(TimeBound::TimeOfDay(start)), Some(TimeBound::TimeOfDay(end))) =
+            (flags.since, flags.until)
+        {
+            if end < start {
+                until = until.map(|until| until + NANOS_PER_DAY);
+            }
+        }
+        since.is_none_or(|since| time >= since) && until.is_none_or(|until| time <= until)
+    }
+}
+
+// Parses a UTCTimestamp, YYYYMMDD-HH:MM:SS with an optional fraction of a second, into
+// nanoseconds since the unix epoch. Digits beyond nanoseconds are ignored.
+pub(crate) fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    // Only ASCII can be a timestamp, which makes every byte index below a char boundary.
+    if !value.is_ascii() {
+        return None;
+    }
+    let (date, time) = value.split_once('-')?;
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, fraction),
+        None => (time, ""),
+    };
+    if date.len() != 8 || time.len() != 8 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number = |digits: &str| -> Option<i64> {
+        if digits.bytes().all(|b| b.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let (year, month, day) = (
+        number(&date[..4])?,
+        number(&date[4..6])?,
+        number(&date[6..])?,
+    );
+    let mut time = time.split(':');
+    let (hour, minute, second) = (
+        number(time.next()?)?,
+        number(time.next()?)?,
+        number(time.next()?)?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    // Seconds go up to 60 to allow for leap seconds.
+    if second > 60 {
+        return None;
+    }
+    let mut nanos = 0;
+    for (i, digit) in fraction.bytes().take(9).enumerate() {
+        nanos += i64::from(digit - b'0') * 10_i64.pow(8 - i as u32);
+    }
+    let days = days_from_civil(year, month, day);
+    // Days past the end of the month, such as 20240230, would roll over into the next one.
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(((days * 24 + hour) * 60 + minute) * 60_000_000_000 + second * 1_000_000_000 + nanos)
+}
+
+// The number of days since 1970-01-01 for a date in the proleptic Gregorian calendar.
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Formats a duration in seconds to the millisecond, as 1.5 seconds -> 1.500s.
+pub(crate) fn format_duration(nanos: i64) -> String {
+    format!("{:.3}s", nanos as f64 / 1e9)
+}
+
+// Formats nanoseconds since the unix epoch as a UTCTimestamp, with as many digits of the fraction
+// as it needs in milliseconds, microseconds or nanoseconds.
+pub(crate) fn format_timestamp(nanos: i64) -> String {
+    let digits = match nanos.rem_euclid(1_000_000_000) {
+        0 => 0,
+        f if f % 1_000_000 == 0 => 3,
+        f if f % 1_000 == 0 => 6,
+        _ => 9,
+    };
+    format_date_time(nanos, digits)
+}
+
+pub(crate) fn format_date_time(nanos: i64, digits: usize) -> String {
+    let (year, month, day) = civil_from_days(nanos.div_euclid(NANOS_PER_DAY));
+    format!(
+        "{year:04}{month:02}{day:02}-{}",
+        format_time_of_day(nanos, digits)
+    )
+}
+
+fn format_time_of_day(nanos: i64, digits: usize) -> String {
+    let nanos = nanos.rem_euclid(NANOS_PER_DAY);
+    let seconds = nanos / 1_000_000_000;
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    format!("{time}{}", format_fraction(nanos, digits))
+}
+
+// The fraction of a second cut to a number of digits, with its point.
+fn format_fraction(nanos: i64, digits: usize) -> String {
+    if digits == 0 {
+        return String::new();
+    }
+    let fraction = nanos.rem_euclid(1_000_000_000) / 10_i64.pow(9 - digits as u32);
+    format!(".{fraction:0digits$}")
+}
+
+// The date of a number of days since 1970-01-01, the inverse of days_from_civil.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::run_lines, Expr, Options, Template};
+
+    #[test]
+    fn timestamp_case() {
+        assert_eq!(
+            parse_timestamp("20240229-23:59:59.5"),
+            Some(1_709_251_199_500_000_000)
+        );
+        assert_eq!(parse_timestamp("20240230-10:00:00"), None);
+        assert_eq!(parse_timestamp("20230229-10:00:00"), None);
+        assert_eq!(parse_timestamp("20240101-25:00:00"), None);
+        assert_eq!(parse_timestamp("202€01-12:00:00"), None);
+        assert_eq!(parse_timestamp("20240101-12:0€:00"), None);
+        assert_eq!(
+            find_time("202€01-12:00:00"),
+            Some((None, 12 * 3_600_000_000_000))
+        );
+
+        // Values that are not timestamps are compared, filtered and printed as they are.
+        let options = Options {
+            delimiter: String::from("|"),
+            strip: true,
+            time: TimeFormat::parse("epoch").unwrap(),
+            filters: vec![Expr::parse(r#"Symbol == "202€01-12:00:00""#, &[]).unwrap()],
+            since: Some(TimeBound::parse("10:00").unwrap()),
+            time_source: TimeSource::Tag(55),
+            ..Options::default()
+        };
+        assert_eq!(
+            run_lines(options, ["35=D|55=202€01-12:00:00"]),
+            "MsgType=D|Symbol=202€01-12:00:00|\n"
+        );
+        let options = Options {
+            sessions: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            run_lines(options, ["35=0|49=A|56=B|52=202€01-12:00:00"]),
+            ""
+        );
+    }
+
+    #[test]
+    fn time_case() {
+        let lines = [
+            "35=W|52=20240701-10:00:00.250|272=20240701|273=09:30:00.123456",
+            "35=D|52=20240701-10:00:01.750|60=20240701-10:00:01",
+        ];
+        let run = |time| {
+            let options = Options {
+                summary: Some(Template::parse("{SendingTime} {MDEntryTime|-} {60|-}").unwrap()),
+                time: TimeFormat::parse(time).unwrap(),
+                ..Options::default()
+            };
+            run_lines(options, lines)
+        };
+        assert_eq!(
+            run("+09:00"),
+            "20240701-19:00:00.250 +09:00 18:30:00.123456 +09:00 -\n\
+             20240701-19:00:01.750 +09:00 - 20240701-19:00:01 +09:00\n"
+        );
+        assert_eq!(run("relative"), "+0.000 -1800.126544 -\n+1.500 - +0\n");
+        assert_eq!(
+            run("epoch"),
+            "1719828000250000000 1719826200123456000 -\n\
+             1719828001750000000 - 1719828001000000000\n"
+        );
+        assert_eq!(
+            run("utc"),
+            "20240701-10:00:00.250 09:30:00.123456 -\n\
+             20240701-10:00:01.750 - 20240701-10:00:01\n"
+        );
+        assert!(TimeFormat::parse("Nowhere/Atlantis").is_err());
+    }
+
+    #[test]
+    fn time_range_case() {
+        let lines = [
+            "09:59:59.500 8=FIX.4.4|35=D|52=20240101-09:59:59.400|11=a",
+            "10:00:00.100 8=FIX.4.4|35=D|52=20240101-10:00:00.050|11=b",
+            "10:04:59.900 8=FIX.4.4|35=D|52=20240101-10:05:00.100|11=c",
+            "8=FIX.4.4|35=D|11=d",
+        ];
+        let run = |since, until: Option<&str>, time_source| {
+            let options = Options {
+                summary: Some(Template::parse("11").unwrap()),
+                since: Some(TimeBound::parse(since).unwrap()),
+                until: until.map(|until| TimeBound::parse(until).unwrap()),
+                time_source,
+                ..Options::default()
+            };
+            run_lines(options, lines)
+        };
+        assert_eq!(run("10:00", Some("10:05"), TimeSource::default()), "b\n");
+        assert_eq!(run("10:00", Some("10:05"), TimeSource::Line), "b\nc\n");
+        assert_eq!(
+            run("2024-01-01T10:00:00", None, TimeSource::Tag(52)),
+            "b\nc\n"
+        );
+        assert_eq!(
+            run("20240101-09:59:59.4", Some("23:00"), TimeSource::default()),
+            "a\nb\nc\n"
+        );
+        assert!(TimeBound::parse("25:00").is_err());
+    }
+}
@@ -0,0 +1,403 @@
+use crate::time::{civil_from_days, days_from_civil};
+use std::{env, fs, path::PathBuf};
+
+/// A time zone from the system's zoneinfo database, such as `Europe/London`, or a fixed offset
+/// from UTC such as `+09:00`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeZone {
+    // Seconds since the unix epoch that each type of local time starts from.
+    transitions: Vec<(i64, usize)>,
+    types: Vec<LocalTime>,
+    // How local time changes after the last transition, from the footer of the zoneinfo file.
+    rule: Option<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LocalTime {
+    offset: i64,
+    abbreviation: String,
+}
+
+// A POSIX TZ rule such as GMT0BST,M3.5.0/1,M10.5.0.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    standard: LocalTime,
+    daylight: Option<(LocalTime, Change, Change)>,
+}
+
+// Daylight saving starts or ends on a weekday of a week of a month, at a number of seconds into
+// the local day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Change {
+    month: i64,
+    week: i64,
+    weekday: i64,
+    time: i64,
+}
+
+impl TimeZone {
+    /// Loads a time zone by its name in the zoneinfo database, found in `$TZDIR` or
+    /// `/usr/share/zoneinfo`. `local` is the zone of `$TZ` or `/etc/localtime`, and `UTC` and
+    /// offsets such as `+09:00` or `-05:30` need no database.
+    pub fn load(name: &str) -> Result<Self, String> {
+        if name.eq_ignore_ascii_case("utc") {
+            return Ok(TimeZone::fixed(0, "UTC"));
+        }
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(TimeZone::fixed(offset, name));
+        }
+        let path = if name.eq_ignore_ascii_case("local") {
+            match env::var("TZ") {
+                Ok(tz) if !tz.is_empty() => return TimeZone::load(tz.trim_start_matches(':')),
+                _ => PathBuf::from("/etc/localtime"),
+            }
+        } else if name.starts_with('/') {
+            PathBuf::from(name)
+        } else {
+            if name.split('/').any(|part| part == "..") {
+                return Err(format!("invalid time zone '{name}'"));
+            }
+            let dir = env::var_os("TZDIR").unwrap_or_else(|| "/usr/share/zoneinfo".into());
+            PathBuf::from(dir).join(name)
+        };
+        let data = fs::read(&path).map_err(|_| format!("unknown time zone '{name}'"))?;
+        parse_tzif(&data).ok_or_else(|| format!("invalid time zone file {}", path.display()))
+    }
+
+    fn fixed(offset: i64, abbreviation: &str) -> Self {
+        TimeZone {
+            transitions: Vec::new(),
+            types: vec![LocalTime {
+                offset,
+                abbreviation: abbreviation.to_string(),
+            }],
+            rule: None,
+        }
+    }
+
+    /// The offset from UTC in seconds and the abbreviation of the local time, such as BST, at a
+    /// time in seconds since the unix epoch.
+    pub fn offset(&self, time: i64) -> (i64, &str) {
+        let i = self
+            .transitions
+            .partition_point(|(start, _)| *start <= time);
+        let local = match &self.rule {
+            Some(rule) if i == self.transitions.len() => rule.local_time(time),
+            // Before the first transition the first type of local time applies.
+            _ if i == 0 => &self.types[0],
+            _ => &self.types[self.transitions[i - 1].1],
+        };
+        (local.offset, &local.abbreviation)
+    }
+}
+
+impl Rule {
+    fn local_time(&self, time: i64) -> &LocalTime {
+        let Some((daylight, start, end)) = &self.daylight else {
+            return &self.standard;
+        };
+        let year = civil_from_days((time + self.standard.offset).div_euclid(86_400)).0;
+        // Daylight saving starts in standard time and ends in daylight time.
+        let start = start.time_in(year) - self.standard.offset;
+        let end = end.time_in(year) - daylight.offset;
+        let is_daylight = if start < end {
+            start <= time && time < end
+        } else {
+            // The southern hemisphere has daylight saving over the new year.
+            !(end <= time && time < start)
+        };
+        if is_daylight {
+            daylight
+        } else {
+            &self.standard
+        }
+    }
+}
+
+impl Change {
+    // The local time in seconds since the unix epoch that the change happens in a year.
+    fn time_in(&self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        // 1970-01-01 was a Thursday.
+        let first_weekday = (first + 4).rem_euclid(7);
+        let mut day = (self.weekday - first_weekday).rem_euclid(7) + (self.week - 1) * 7;
+        let next_month = match self.month {
+            12 => days_from_civil(year + 1, 1, 1),
+            month => days_from_civil(year, month + 1, 1),
+        };
+        // The fifth week means the last one, which may only have four.
+        while first + day >= next_month {
+            day -= 7;
+        }
+        (first + day) * 86_400 + self.time
+    }
+}
+
+// Offsets written as +HH:MM, -HH:MM, +HHMM or +HH.
+fn parse_fixed_offset(input: &str) -> Option<i64> {
+    let sign = match input.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = input[1..].replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || !matches!(digits.len(), 2 | 4) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits.get(2..).unwrap_or("0").parse().ok()?;
+    (hours <= 23 && minutes <= 59).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+// Reads the TZif format described in RFC 8536, using the 64-bit data of version 2 and later.
+fn parse_tzif(data: &[u8]) -> Option<TimeZone> {
+    let header = |data: &[u8]| -> Option<[usize; 6]> {
+        if data.get(..4)? != b"TZif" {
+            return None;
+        }
+        let mut counts = [0; 6];
+        for (i, count) in counts.iter_mut().enumerate() {
+            let start = 20 + i * 4;
+            *count = u32::from_be_bytes(data.get(start..start + 4)?.try_into().ok()?) as usize;
+        }
+        Some(counts)
+    };
+    let [is_ut, is_std, leap, time, types, chars] = header(data)?;
+    let version = *data.get(4)?;
+    let (data, time_size) = if version >= b'2' {
+        let v1_len = time * 5 + types * 6 + chars + leap * 8 + is_std + is_ut;
+        (data.get(44 + v1_len..)?, 8)
+    } else {
+        (data, 4)
+    };
+    let [is_ut, is_std, leap, time, types, chars] = header(data)?;
+    let mut position = 44;
+    let mut take = |len: usize| -> Option<&[u8]> {
+        let bytes = data.get(position..position + len)?;
+        position += len;
+        Some(bytes)
+    };
+    let times = take(time * time_size)?;
+    let indices = take(time)?;
+    let infos = take(types * 6)?;
+    let abbreviations = take(chars)?;
+    take(leap * (time_size + 4) + is_std + is_ut)?;
+    let footer = data.get(position..).unwrap_or_default();
+
+    let transitions = times
+        .chunks(time_size)
+        .zip(indices)
+        .map(|(time, &index)| {
+            let time = match time_size {
+                8 => i64::from_be_bytes(time.try_into().unwrap()),
+                _ => i64::from(i32::from_be_bytes(time.try_into().unwrap())),
+            };
+            (time, usize::from(index))
+        })
+        .collect::<Vec<_>>();
+    let types = infos
+        .chunks(6)
+        .map(|info| {
+            let offset = i64::from(i32::from_be_bytes(info[..4].try_into().unwrap()));
+            let start = usize::from(info[5]).min(abbreviations.len());
+            let end = abbreviations[start..]
+                .iter()
+                .position(|&b| b == 0)
+                .map_or(abbreviations.len(), |end| start + end);
+            let abbreviation = String::from_utf8_lossy(&abbreviations[start..end]).into_owned();
+            LocalTime {
+                offset,
+                abbreviation,
+            }
+        })
+        .collect::<Vec<_>>();
+    if types.is_empty() || transitions.iter().any(|(_, index)| *index >= types.len()) {
+        return None;
+    }
+    let rule = std::str::from_utf8(footer)
+        .ok()
+        .and_then(|footer| parse_rule(footer.trim()));
+    Some(TimeZone {
+        transitions,
+        types,
+        rule,
+    })
+}
+
+// Only rules with daylight saving given as Mm.w.d are understood, as used by every current zone.
+fn parse_rule(input: &str) -> Option<Rule> {
+    let (standard_name, rest) = parse_name(input)?;
+    let (standard_offset, mut rest) = parse_time(rest)?;
+    // POSIX offsets are west of UTC, so they have the opposite sign.
+    let standard = LocalTime {
+        offset: -standard_offset,
+        abbreviation: standard_name,
+    };
+    if rest.is_empty() {
+        return Some(Rule {
+            standard,
+            daylight: None,
+        });
+    }
+    let (daylight_name, remaining) = parse_name(rest)?;
+    rest = remaining;
+    let daylight_offset = if rest.starts_with(',') {
+        standard.offset + 3600
+    } else {
+        let (offset, remaining) = parse_time(rest)?;
+        rest = remaining;
+        -offset
+    };
+    let mut changes = rest.strip_prefix(',')?.split(',');
+    let (start, end) = (
+        parse_change(changes.next()?)?,
+        parse_change(changes.next()?)?,
+    );
+    Some(Rule {
+        standard,
+        daylight: Some((
+            LocalTime {
+                offset: daylight_offset,
+                abbreviation: daylight_name,
+            },
+            start,
+            end,
+        )),
+    })
+}
+
+// Names are letters, or anything between < and > such as <+09>.
+fn parse_name(input: &str) -> Option<(String, &str)> {
+    let (name, rest) = match input.strip_prefix('<') {
+        Some(quoted) => {
+            let end = quoted.find('>')?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let end = input
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(input.len());
+            input.split_at(end)
+        }
+    };
+    (!name.is_empty()).then(|| (name.to_string(), rest))
+}
+
+fn parse_change(input: &str) -> Option<Change> {
+    let (date, time) = match input.split_once('/') {
+        Some((date, time)) => (date, parse_time(time)?.0),
+        None => (input, 2 * 3600),
+    };
+    let mut parts = date.strip_prefix('M')?.split('.');
+    let mut number = || parts.next()?.parse::<i64>().ok();
+    let (month, week, weekday) = (number()?, number()?, number()?);
+    let valid = (1..=12).contains(&month) && (1..=5).contains(&week) && (0..=6).contains(&weekday);
+    valid.then_some(Change {
+        month,
+        week,
+        weekday,
+        time,
+    })
+}
+
+// Parses [+-]hh[:mm[:ss]] into seconds, returning what follows it.
+fn parse_time(input: &str) -> Option<(i64, &str)> {
+    let (sign, input) = match input.as_bytes().first()? {
+        b'-' => (-1, &input[1..]),
+        b'+' => (1, &input[1..]),
+        _ => (1, input),
+    };
+    let end = input
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(input.len());
+    let mut seconds = 0;
+    for (i, part) in input[..end].split(':').enumerate().take(3) {
+        seconds += part.parse::<i64>().ok()? * [3600, 60, 1][i];
+    }
+    Some((sign * seconds, &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes a version 2 zoneinfo file, with no version 1 data, for local times given as offset,
+    // is DST and abbreviation.
+    fn tzif(transitions: &[(i64, u8)], types: &[(i32, u8, &str)], footer: &str) -> Vec<u8> {
+        let header = |counts: [usize; 6]| {
+            let mut header = b"TZif2".to_vec();
+            header.extend([0; 15]);
+            for count in counts {
+                header.extend((count as u32).to_be_bytes());
+            }
+            header
+        };
+        let mut abbreviations = Vec::new();
+        let mut infos = Vec::new();
+        for (offset, is_dst, abbreviation) in types {
+            infos.extend(offset.to_be_bytes());
+            infos.extend([*is_dst, abbreviations.len() as u8]);
+            abbreviations.extend(abbreviation.bytes().chain([0]));
+        }
+        let mut data = header([0; 6]);
+        data.extend(header([
+            0,
+            0,
+            0,
+            transitions.len(),
+            types.len(),
+            abbreviations.len(),
+        ]));
+        data.extend(transitions.iter().flat_map(|(time, _)| time.to_be_bytes()));
+        data.extend(transitions.iter().map(|(_, index)| index));
+        data.extend(infos);
+        data.extend(abbreviations);
+        data.extend(format!("\n{footer}\n").bytes());
+        data
+    }
+
+    #[test]
+    fn zone_case() {
+        // The transitions of 2023, then the rule for later years.
+        let london = parse_tzif(&tzif(
+            &[(1_679_792_400, 1), (1_698_541_200, 0)],
+            &[(0, 0, "GMT"), (3600, 1, "BST")],
+            "GMT0BST,M3.5.0/1,M10.5.0",
+        ))
+        .unwrap();
+        let sydney = parse_tzif(&tzif(
+            &[],
+            &[(36_000, 0, "AEST")],
+            "AEST-10AEDT,M10.1.0,M4.1.0/3",
+        ))
+        .unwrap();
+
+        // Before the first transition, and either side of each transition.
+        assert_eq!(london.offset(1_672_531_200), (0, "GMT"));
+        assert_eq!(london.offset(1_679_792_399), (0, "GMT"));
+        assert_eq!(london.offset(1_679_792_400), (3600, "BST"));
+        assert_eq!(london.offset(1_688_169_600), (3600, "BST"));
+        assert_eq!(london.offset(1_698_541_199), (3600, "BST"));
+        assert_eq!(london.offset(1_698_541_200), (0, "GMT"));
+        // After the last transition the rule gives the changes at 01:00 UTC on the last Sundays
+        // of March and October.
+        assert_eq!(london.offset(1_701_388_800), (0, "GMT"));
+        assert_eq!(london.offset(1_711_846_799), (0, "GMT"));
+        assert_eq!(london.offset(1_711_846_800), (3600, "BST"));
+        assert_eq!(london.offset(1_729_990_799), (3600, "BST"));
+        assert_eq!(london.offset(1_729_990_800), (0, "GMT"));
+        // Daylight saving over the new year ends at 03:00 local time on the first Sunday of April.
+        assert_eq!(sydney.offset(1_705_276_800), (39_600, "AEDT"));
+        assert_eq!(sydney.offset(1_712_419_199), (39_600, "AEDT"));
+        assert_eq!(sydney.offset(1_712_419_200), (36_000, "AEST"));
+        assert_eq!(sydney.offset(1_719_792_000), (36_000, "AEST"));
+
+        assert_eq!(
+            TimeZone::load("-05:30").unwrap().offset(0),
+            (-19_800, "-05:30")
+        );
+        assert_eq!(TimeZone::load("utc").unwrap().offset(0), (0, "UTC"));
+        assert!(TimeZone::load("Europe/../London").is_err());
+        assert_eq!(parse_fixed_offset("+24:00"), None);
+    }
+}